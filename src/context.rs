@@ -0,0 +1,54 @@
+//! Access to the simulation state shared through Leptos contexts.
+
+use leptos::prelude::*;
+use reactive_stores::Store;
+
+use crate::GlobalState;
+
+/// Provides the [`GlobalState`] store to its children.
+///
+/// Mounting it inside another `AppState` panics, because components would
+/// silently read whichever store is closest. With `reuse_existing`, the outer
+/// store is kept instead and `state` is dropped.
+#[component]
+pub fn AppState(
+    state: GlobalState,
+    #[prop(optional)] reuse_existing: bool,
+    children: Children,
+) -> impl IntoView {
+    if use_context::<Store<GlobalState>>().is_some() {
+        if !reuse_existing {
+            panic!(
+                "AppState is mounted inside another AppState, which would provide a second \
+                 Store<GlobalState>. Remove the inner provider or set `reuse_existing`."
+            );
+        }
+        log::warn!("AppState reuses the Store<GlobalState> of an enclosing AppState");
+    } else {
+        provide_context(Store::new(state));
+        provide_context(ForkedState(RwSignal::new(None)));
+    }
+    children()
+}
+
+/// A second copy of the state, see [`GlobalState::fork`].
+#[derive(Debug, Clone, Copy)]
+struct ForkedState(RwSignal<Option<Store<GlobalState>>>);
+
+/// The [`GlobalState`] store provided by the enclosing [`AppState`].
+pub fn use_global_state() -> Store<GlobalState> {
+    use_context().unwrap_or_else(|| {
+        panic!("No Store<GlobalState> in context, the component must be rendered inside <AppState>")
+    })
+}
+
+/// The slot for the fork of the [`GlobalState`], empty until a fork is created.
+pub fn use_forked_state() -> RwSignal<Option<Store<GlobalState>>> {
+    use_context::<ForkedState>()
+        .unwrap_or_else(|| {
+            panic!(
+                "No forked state slot in context, the component must be rendered inside <AppState>"
+            )
+        })
+        .0
+}
@@ -0,0 +1,132 @@
+//! A menu of actions at the cursor, rendered by one [`ContextMenuHost`].
+//!
+//! Components open menus with [`open_context_menu`] instead of keeping their own
+//! open/close state. There is only one menu, so opening one closes the menu that was open.
+
+use std::sync::Arc;
+
+use leptos::{ev, html::Div, prelude::*};
+
+use crate::styles;
+
+/// A position in client coordinates, like that of the event opening a menu.
+pub type ClientPosition = (i32, i32);
+
+/// One entry of a [`ContextMenu`].
+#[derive(Clone)]
+pub struct ContextMenuAction {
+    label: &'static str,
+    /// Called when the entry is chosen, before the menu closes.
+    /// Kept in an `Arc` rather than a `Callback`, so that it is dropped with the menu.
+    on_select: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl ContextMenuAction {
+    pub fn new(label: &'static str, on_select: impl Fn() + Send + Sync + 'static) -> Self {
+        Self {
+            label,
+            on_select: Arc::new(on_select),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct OpenMenu {
+    position: ClientPosition,
+    actions: Vec<ContextMenuAction>,
+}
+
+#[derive(Clone, Copy)]
+struct OpenContextMenu(RwSignal<Option<OpenMenu>>);
+
+/// Provides the state that [`open_context_menu`] and [`ContextMenuHost`] use.
+pub fn provide_context_menu() {
+    provide_context(OpenContextMenu(RwSignal::new(None)));
+}
+
+fn use_context_menu() -> RwSignal<Option<OpenMenu>> {
+    use_context::<OpenContextMenu>()
+        .unwrap_or_else(|| {
+            panic!("No OpenContextMenu in context, call provide_context_menu() in an ancestor")
+        })
+        .0
+}
+
+/// Show a menu of `actions` at `position`, closing the open one.
+pub fn open_context_menu(position: ClientPosition, actions: Vec<ContextMenuAction>) {
+    use_context_menu().set(Some(OpenMenu { position, actions }));
+}
+
+/// Renders the open menu, or nothing if no menu is open.
+#[component]
+pub fn ContextMenuHost() -> impl IntoView {
+    let open = use_context_menu();
+    let close = Callback::new(move |()| open.set(None));
+
+    move || {
+        open.get().map(|OpenMenu { position, actions }| {
+            view! { <ContextMenu position actions on_close=close /> }
+        })
+    }
+}
+
+/// The buttons of `actions` at `position`, over a transparent backdrop.
+/// A click outside of the menu, or Escape, closes it without choosing an action.
+#[component]
+pub fn ContextMenu(
+    position: ClientPosition,
+    actions: Vec<ContextMenuAction>,
+    #[prop(into)] on_close: Callback<()>,
+) -> impl IntoView {
+    let menu = NodeRef::<Div>::new();
+    Effect::new(move || {
+        if let Some(menu) = menu.get() {
+            let _ = menu.focus();
+        }
+    });
+
+    let items = actions
+        .into_iter()
+        .map(|action| {
+            let choose = move |_| {
+                (action.on_select)();
+                on_close.run(());
+            };
+            view! {
+                <button role="menuitem" on:click=choose>
+                    {action.label}
+                </button>
+            }
+        })
+        .collect_view();
+    let keydown = move |event: ev::KeyboardEvent| {
+        if event.key() == "Escape" {
+            event.prevent_default();
+            on_close.run(());
+        }
+    };
+    let (left, top) = position;
+
+    view! {
+        <div
+            style="position: fixed; inset: 0"
+            on:click=move |_| on_close.run(())
+            on:contextmenu=move |event: ev::MouseEvent| {
+                event.prevent_default();
+                on_close.run(());
+            }
+        >
+            <div
+                node_ref=menu
+                class=styles::CONTEXT_MENU
+                role="menu"
+                tabindex="-1"
+                style=format!("position: fixed; left: {left}px; top: {top}px")
+                on:click=|event| event.stop_propagation()
+                on:keydown=keydown
+            >
+                {items}
+            </div>
+        </div>
+    }
+}
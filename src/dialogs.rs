@@ -0,0 +1,228 @@
+//! A stack of modal dialogs, rendered by one [`DialogHost`].
+//!
+//! Components open dialogs with [`open_dialog`] instead of keeping their own
+//! open/close state. Opening a dialog while another is shown stacks it on top.
+
+use leptos::{ev, html::Div, prelude::*};
+use reactive_stores::Store;
+use wasm_bindgen::JsCast;
+
+use crate::{AddModifierDialog, GameOverDialog, PersonId};
+
+/// Distinguishes dialogs so that a handle closes its own dialog, wherever it is in the stack.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct DialogId(u64);
+
+/// What a dialog shows.
+#[derive(Debug, Clone)]
+pub enum DialogRequest {
+    /// Asks a yes-or-no question. `on_choice` is called with `true` if the user confirmed,
+    /// or `false` if they cancelled or dismissed the dialog.
+    Confirm {
+        title: String,
+        message: String,
+        confirm_label: &'static str,
+        on_choice: Callback<bool>,
+    },
+    /// Shown while the game is over. Cannot be dismissed, only resolved by its buttons.
+    GameOver,
+    /// Picks the kind and magnitude of a modifier to add to the person.
+    AddModifier { person_id: PersonId },
+}
+
+impl DialogRequest {
+    fn title(&self) -> String {
+        match self {
+            Self::Confirm { title, .. } => title.clone(),
+            Self::GameOver => "Game over".to_string(),
+            Self::AddModifier { person_id } => format!("Add a modifier to person {person_id}"),
+        }
+    }
+
+    fn is_dismissable(&self) -> bool {
+        match self {
+            Self::Confirm { .. } | Self::AddModifier { .. } => true,
+            Self::GameOver => false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Store)]
+pub struct DialogManager {
+    /// The open dialogs, the topmost last.
+    stack: Vec<(DialogId, DialogRequest)>,
+    next_id: u64,
+}
+
+/// Closes the dialog it was returned for.
+#[derive(Debug, Clone, Copy)]
+pub struct DialogHandle {
+    id: DialogId,
+    dialogs: Store<DialogManager>,
+}
+
+impl DialogHandle {
+    /// Remove the dialog from the stack. Does nothing if it was already closed.
+    pub fn close(self) {
+        self.dialogs
+            .stack()
+            .write()
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+/// Provides the [`DialogManager`] store that [`open_dialog`] and [`DialogHost`] use.
+pub fn provide_dialogs() {
+    provide_context(Store::new(DialogManager::default()));
+}
+
+fn use_dialogs() -> Store<DialogManager> {
+    use_context().unwrap_or_else(|| {
+        panic!("No Store<DialogManager> in context, call provide_dialogs() in an ancestor")
+    })
+}
+
+/// Show a dialog on top of any open ones.
+pub fn open_dialog(request: DialogRequest) -> DialogHandle {
+    let dialogs = use_dialogs();
+    let id = DialogId(dialogs.next_id().get_untracked());
+    *dialogs.next_id().write() += 1;
+    dialogs.stack().write().push((id, request));
+    DialogHandle { id, dialogs }
+}
+
+/// Renders the topmost dialog over a backdrop, or nothing if no dialog is open.
+#[component]
+pub fn DialogHost() -> impl IntoView {
+    let dialogs = use_dialogs();
+    // Only re-rendered when the topmost dialog changes, not for changes below it.
+    let top = Memo::new(move |_| dialogs.stack().read().last().map(|(id, _)| *id));
+
+    move || {
+        top.get().map(|id| {
+            let request = dialogs
+                .stack()
+                .read_untracked()
+                .iter()
+                .find(|(dialog_id, _)| *dialog_id == id)
+                .map(|(_, request)| request.clone())
+                .expect("the top dialog is in the stack");
+            view! { <DialogFrame handle=DialogHandle { id, dialogs } request /> }
+        })
+    }
+}
+
+#[component]
+fn DialogFrame(handle: DialogHandle, request: DialogRequest) -> impl IntoView {
+    let frame = NodeRef::<Div>::new();
+    Effect::new(move || {
+        if let Some(frame) = frame.get() {
+            let _ = frame.focus();
+        }
+    });
+
+    let dismiss = {
+        let request = request.clone();
+        move || {
+            if !request.is_dismissable() {
+                return;
+            }
+            handle.close();
+            if let DialogRequest::Confirm { on_choice, .. } = &request {
+                on_choice.run(false);
+            }
+        }
+    };
+    let keydown = {
+        let dismiss = dismiss.clone();
+        move |event: ev::KeyboardEvent| match event.key().as_str() {
+            "Escape" => {
+                event.prevent_default();
+                dismiss();
+            }
+            "Tab" => {
+                if let Some(frame) = frame.get_untracked() {
+                    trap_focus(&frame, &event);
+                }
+            }
+            _ => {}
+        }
+    };
+
+    let title = request.title();
+    let label = title.clone();
+    let body = match request {
+        DialogRequest::Confirm {
+            message,
+            confirm_label,
+            on_choice,
+            ..
+        } => {
+            let choose = move |choice: bool| {
+                handle.close();
+                on_choice.run(choice);
+            };
+            view! {
+                <p>{message}</p>
+                <button on:click=move |_| choose(false)>Cancel</button>
+                <button on:click=move |_| choose(true)>{confirm_label}</button>
+            }
+            .into_any()
+        }
+        DialogRequest::GameOver => view! { <GameOverDialog /> }.into_any(),
+        DialogRequest::AddModifier { person_id } => {
+            view! { <AddModifierDialog person_id handle /> }.into_any()
+        }
+    };
+
+    view! {
+        <div
+            style="position: fixed; inset: 0; background-color: rgba(0, 0, 0, 0.7); display: flex; align-items: center; justify-content: center"
+            on:click=move |_| dismiss()
+        >
+            <div
+                node_ref=frame
+                role="dialog"
+                aria-modal="true"
+                aria-label=label
+                tabindex="-1"
+                style="background-color: var(--background-color); padding: 1em"
+                on:click=|event| event.stop_propagation()
+                on:keydown=keydown
+            >
+                <h2>{title}</h2>
+                {body}
+            </div>
+        </div>
+    }
+}
+
+/// Keeps Tab and Shift+Tab cycling through the focusable elements of the dialog.
+fn trap_focus(frame: &web_sys::HtmlDivElement, event: &ev::KeyboardEvent) {
+    let Ok(focusable) = frame.query_selector_all("button, input, select, textarea, [href]") else {
+        return;
+    };
+    let element = |index| {
+        focusable
+            .item(index)
+            .and_then(|node| node.dyn_into::<web_sys::HtmlElement>().ok())
+    };
+    let (Some(first), Some(last)) = (element(0), element(focusable.length().saturating_sub(1)))
+    else {
+        event.prevent_default();
+        return;
+    };
+    let active = document().active_element();
+    let is_active = |element: &web_sys::HtmlElement| {
+        active
+            .as_ref()
+            .is_some_and(|active| active == element.unchecked_ref::<web_sys::Element>())
+    };
+    if event.shift_key() && (is_active(&first) || active.as_deref() == Some(frame)) {
+        event.prevent_default();
+        let _ = last.focus();
+    } else if !event.shift_key() && is_active(&last) {
+        event.prevent_default();
+        let _ = first.focus();
+    }
+}
@@ -0,0 +1,6793 @@
+mod context;
+mod context_menu;
+mod dialogs;
+pub mod sim;
+mod styles;
+#[cfg(test)]
+mod test_support;
+#[cfg(test)]
+mod tests;
+mod toasts;
+
+use std::{
+    cmp::Ordering as CmpOrdering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+    hash::Hash,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use context::{use_forked_state, use_global_state, AppState};
+use context_menu::{
+    open_context_menu, provide_context_menu, ClientPosition, ContextMenuAction, ContextMenuHost,
+};
+use dialogs::{open_dialog, provide_dialogs, DialogHandle, DialogHost, DialogRequest};
+use leptos::{prelude::*, tachys::reactive_graph::OwnedView};
+use log::Level;
+use reactive_stores::{AtKeyed, Field, Store, StoreFieldIterator};
+use send_wrapper::SendWrapper;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use toasts::{provide_toasts, show_icon_undo_toast, show_undo_toast, ToastHost};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::MediaQueryListEvent;
+
+#[derive(Debug, Clone, Store, Serialize, Deserialize)]
+pub struct GlobalState {
+    /// The current week, starting at zero and incremented by each finished week.
+    week: u64,
+    config: SimConfig,
+    rng: SimRng,
+    #[store(key: CityId = |row| row.key())]
+    cities: Vec<City>,
+    /// Violations found by the last invariant check. Only populated in debug builds.
+    #[serde(skip)]
+    invariant_violations: Vec<InvariantViolation>,
+    /// In read-only mode, only the auto-advance loop may change the state.
+    #[serde(skip)]
+    read_only: bool,
+    /// The fast-forward that is currently running, if any.
+    #[serde(skip)]
+    fast_forward: Option<FastForward>,
+    /// What happened in the most recently finished week.
+    #[serde(skip)]
+    last_week_outcome: Option<WeekOutcome>,
+    goals: Vec<Goal>,
+    /// Set when the player keeps playing after failing a goal. Goals are no longer evaluated.
+    sandbox: bool,
+    watchers: Vec<Watcher>,
+    next_watcher_id: u64,
+    /// The RNG draws of every week finished while auditing, oldest first.
+    #[serde(skip)]
+    rng_audit_log: Vec<(u64, RngDrawCounts)>,
+    /// The modifier counts of the last [`MODIFIER_STATS_WEEKS`] finished weeks, oldest first.
+    modifier_stats: VecDeque<(u64, ModifierStats)>,
+    /// Modifiers added by hand since the last finished week, counted toward the next one.
+    pending_modifier_stats: ModifierStats,
+    /// Hands out the ids of the people and modifiers of this game.
+    ids: IdAllocator,
+}
+
+impl GlobalState {
+    pub fn new_debug_instance(config: SimConfig) -> Self {
+        let mut ids = IdAllocator::default();
+        Self {
+            week: 0,
+            rng: SimRng::new(42),
+            cities: vec![
+                City::new(CityId(1), "Northbrook", &config, &mut ids),
+                City::new(CityId(2), "Southmere", &config, &mut ids),
+            ],
+            config,
+            invariant_violations: Vec::new(),
+            read_only: false,
+            fast_forward: None,
+            last_week_outcome: None,
+            goals: Goal::default_goals(),
+            sandbox: false,
+            watchers: Vec::new(),
+            next_watcher_id: 1,
+            rng_audit_log: Vec::new(),
+            modifier_stats: VecDeque::new(),
+            pending_modifier_stats: ModifierStats::new(),
+            ids,
+        }
+    }
+
+    /// A deep copy to run forward independently, for example to compare a changed config.
+    /// The copy shares no state with the original and has its own copy of the RNG state.
+    pub fn fork(&self) -> Self {
+        Self {
+            fast_forward: None,
+            ..self.clone()
+        }
+    }
+
+    /// The game as JSON, for saving. What only matters while the game runs is left out:
+    /// the invariant violations, read-only mode, the fast-forward, the last week's outcome
+    /// and the RNG audit.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("every map in the state has keys that JSON supports")
+    }
+
+    /// Read a game written by [`GlobalState::to_json`].
+    /// Run [`GlobalState::check_invariants`] on its store, as the text may have been edited.
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        let mut state: Self = serde_json::from_str(text)?;
+        for city in &mut state.cities {
+            city.population.rebuild_index();
+        }
+        Ok(state)
+    }
+
+    /// Read a game written by [`GlobalState::to_json`] to continue playing it.
+    /// The config of the save is only used with `use_saved_config`, otherwise the game
+    /// continues with `local_config`, so that loading keeps the local tuning.
+    pub fn from_save(
+        text: &str,
+        local_config: SimConfig,
+        use_saved_config: bool,
+    ) -> Result<Self, serde_json::Error> {
+        let mut state = Self::from_json(text)?;
+        if !use_saved_config {
+            state.config = local_config;
+        }
+        Ok(state)
+    }
+
+    /// Whether a goal failed and the player has not chosen to continue in sandbox mode.
+    /// Weeks do not advance while the game is over.
+    pub fn is_game_over(&self) -> bool {
+        !self.sandbox
+            && self
+                .goals
+                .iter()
+                .any(|goal| goal.status == GoalStatus::Failed)
+    }
+
+    /// Keep playing after a goal failed, without evaluating goals anymore.
+    pub fn continue_in_sandbox(this: Store<Self>) {
+        this.sandbox().set(true);
+    }
+
+    /// Finish the week on behalf of the user. Does nothing in read-only mode.
+    pub fn finish_week(this: Store<Self>) {
+        if Self::reject_if_read_only(this, "finish the week") {
+            return;
+        }
+        if this.read_untracked().is_game_over() {
+            log::warn!("Refusing to finish the week after the game is over");
+            return;
+        }
+        Self::advance_week(this);
+    }
+
+    /// Finish `weeks` weeks in chunks of [`FAST_FORWARD_CHUNK_WEEKS`],
+    /// yielding to the browser between chunks so the tab stays responsive.
+    ///
+    /// Returns false and does nothing if a fast-forward is already running.
+    pub fn finish_weeks_async(this: Store<Self>, weeks: u64) -> bool {
+        if Self::reject_if_read_only(this, "fast-forward") {
+            return false;
+        }
+        Self::start_fast_forward(this, weeks)
+    }
+
+    /// Like [`GlobalState::finish_weeks_async`], but even in read-only mode.
+    /// Only the auto-advance loop should call this directly.
+    fn start_fast_forward(this: Store<Self>, weeks: u64) -> bool {
+        if this.fast_forward().read_untracked().is_some() {
+            log::warn!("Refusing to start a fast-forward while another one is running");
+            return false;
+        }
+
+        this.fast_forward().set(Some(FastForward {
+            progress: Progress {
+                done: 0,
+                total: weeks,
+            },
+            cancel_requested: false,
+        }));
+        set_timeout(move || Self::run_fast_forward_chunk(this), Duration::ZERO);
+        true
+    }
+
+    /// Stop the running fast-forward after its current chunk.
+    pub fn cancel_fast_forward(this: Store<Self>) {
+        if let Some(fast_forward) = this.fast_forward().write().as_mut() {
+            fast_forward.cancel_requested = true;
+        }
+    }
+
+    fn run_fast_forward_chunk(this: Store<Self>) {
+        if Self::fast_forward_step(this) {
+            set_timeout(move || Self::run_fast_forward_chunk(this), Duration::ZERO);
+        }
+    }
+
+    /// Finish the next chunk of the running fast-forward, or end it if it is done or cancelled.
+    /// Returns whether another chunk should follow.
+    fn fast_forward_step(this: Store<Self>) -> bool {
+        // The store is gone if a new game started since the last chunk.
+        let Some(Some(fast_forward)) = this.fast_forward().try_get_untracked() else {
+            return false;
+        };
+        let Progress { done, total } = fast_forward.progress;
+        if fast_forward.cancel_requested || done >= total || this.read_untracked().is_game_over() {
+            this.fast_forward().set(None);
+            return false;
+        }
+
+        let chunk = (total - done).min(FAST_FORWARD_CHUNK_WEEKS);
+        for _ in 0..chunk {
+            Self::advance_week(this);
+            if this.read_untracked().is_game_over() {
+                break;
+            }
+        }
+        if let Some(fast_forward) = this.fast_forward().write().as_mut() {
+            fast_forward.progress.done += chunk;
+        }
+        true
+    }
+
+    /// Advance the simulation by one week, even in read-only mode.
+    /// Only the auto-advance loop should call this directly.
+    fn advance_week(this: Store<Self>) {
+        if write_during_read(|| "week".to_string()) {
+            return;
+        }
+        *this.week().write() += 1;
+        let week = this.week().get_untracked();
+        record_mutation(
+            week,
+            || "week".to_string(),
+            MutationKind::Update,
+            "GlobalState::advance_week",
+        );
+        let config = this.config().get_untracked();
+        let mut rng = this.rng().get_untracked();
+        let mut ids = this.ids().get_untracked();
+        let mut outcome = WeekOutcome::new(week);
+        if !this.pending_modifier_stats().read_untracked().is_empty() {
+            outcome.modifier_stats = std::mem::take(&mut *this.pending_modifier_stats().write());
+        }
+        let mut ctx = TickContext {
+            state: this,
+            config: &config,
+            rng: &mut rng,
+            ids: &mut ids,
+            week,
+            outcome: &mut outcome,
+        };
+        for system in TICK_SYSTEMS {
+            if config.disabled_systems.contains(system.name()) {
+                continue;
+            }
+            system.run(&mut ctx);
+        }
+        if let Some(draws) = rng.take_draw_counts() {
+            this.rng_audit_log().write().push((week, draws.clone()));
+            outcome.rng_draws = Some(draws);
+        }
+        this.rng().set(rng);
+        this.ids().set(ids);
+        Self::record_modifier_stats(this, &mut outcome);
+        let (population, average_happiness) = Self::overall_happiness(this);
+        outcome.average_happiness = average_happiness;
+        // Goals only look at the state after all systems ran.
+        Self::evaluate_goals(this, population, &mut outcome);
+        Self::evaluate_watchers(this, &mut outcome);
+        this.last_week_outcome().set(Some(outcome));
+        Self::check_invariants(this);
+    }
+
+    /// Complete the modifier counts of the outcome with the active modifiers after all systems ran,
+    /// and add them to the history.
+    fn record_modifier_stats(this: Store<Self>, outcome: &mut WeekOutcome) {
+        let stats = &mut outcome.modifier_stats;
+        for city in &this.read_untracked().cities {
+            for person in &city.population.people {
+                for modifier in &person.happiness.happiness_modifiers {
+                    let kind = stats.entry(modifier.spec).or_default();
+                    kind.active += 1;
+                    kind.magnitude_sum += modifier.current_magnitude();
+                }
+            }
+        }
+        this.modifier_stats().update(|history| {
+            if history.len() >= MODIFIER_STATS_WEEKS {
+                history.pop_front();
+            }
+            history.push_back((outcome.week, stats.clone()));
+        });
+    }
+
+    /// Run `f` with the id allocator of the state, writing it back afterwards.
+    /// Ids allocated through the state while `f` runs would be handed out again.
+    fn with_ids<T>(this: Store<Self>, f: impl FnOnce(&mut IdAllocator) -> T) -> T {
+        let mut ids = this.ids().get_untracked();
+        let result = f(&mut ids);
+        this.ids().set(ids);
+        result
+    }
+
+    /// Count modifiers added outside of a tick toward the next finished week.
+    fn count_added_modifiers(this: Store<Self>, spec: ModifierSpecId, count: usize) {
+        if count > 0 {
+            this.pending_modifier_stats()
+                .update(|pending| pending.entry(spec).or_default().added += count as u32);
+        }
+    }
+
+    /// Start or stop counting the RNG draws of each week. Either way, the audit log is cleared.
+    pub fn set_rng_audit(this: Store<Self>, enabled: bool) {
+        this.rng().write().set_audit(enabled);
+        this.rng_audit_log().write().clear();
+    }
+
+    /// Update the goals still in progress, adding the ones that finished to the outcome.
+    fn evaluate_goals(this: Store<Self>, population: usize, outcome: &mut WeekOutcome) {
+        if this.sandbox().get_untracked() {
+            return;
+        }
+        for goal in this.goals().write().iter_mut() {
+            if goal.status == GoalStatus::InProgress {
+                goal.status =
+                    goal.kind
+                        .evaluate(outcome.week, population, outcome.average_happiness);
+                if goal.status != GoalStatus::InProgress {
+                    outcome.finished_goals.push(goal.clone());
+                }
+            }
+        }
+    }
+
+    /// Check every watcher against the post-tick totals, adding the crossings to the outcome.
+    fn evaluate_watchers(this: Store<Self>, outcome: &mut WeekOutcome) {
+        if this.watchers().read_untracked().is_empty() {
+            return;
+        }
+        let totals: Vec<_> = this
+            .read_untracked()
+            .cities
+            .iter()
+            .flat_map(|city| city.population.people_in_id_order())
+            .map(|person| (person.id, person.happiness.current_total()))
+            .collect();
+        for watcher in this.watchers().write().iter_mut() {
+            for &(person_id, total) in &totals {
+                if watcher.observe(person_id, total) {
+                    outcome.watcher_alerts.push(WatcherAlert {
+                        watcher_id: watcher.id,
+                        person_id,
+                        total,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn add_watcher(
+        this: Store<Self>,
+        threshold: f64,
+        direction: CrossingDirection,
+        scope: WatcherScope,
+        hysteresis: f64,
+    ) {
+        if Self::reject_if_read_only(this, "add a watcher") {
+            return;
+        }
+        let id = WatcherId(this.next_watcher_id().get_untracked());
+        *this.next_watcher_id().write() += 1;
+        this.watchers().write().push(Watcher {
+            id,
+            threshold,
+            direction,
+            scope,
+            hysteresis,
+            fired_for: BTreeSet::new(),
+        });
+    }
+
+    pub fn remove_watcher(this: Store<Self>, watcher_id: WatcherId) {
+        if Self::reject_if_read_only(this, "remove a watcher") {
+            return;
+        }
+        this.watchers()
+            .write()
+            .retain(|watcher| watcher.id != watcher_id);
+    }
+
+    /// The number of people in all cities and their average happiness,
+    /// or `None` if nobody lives anywhere.
+    pub fn overall_happiness(this: Store<Self>) -> (usize, Option<f64>) {
+        let mut population = 0;
+        let mut total_happiness = 0.0;
+        for city in this.cities().iter_unkeyed() {
+            let count = city.population().people().read().len();
+            if let Some(average) = Population::average_happiness(city.population().into()) {
+                population += count;
+                total_happiness += average * count as f64;
+            }
+        }
+        let average = (population > 0).then(|| total_happiness / population as f64);
+        (population, average)
+    }
+
+    pub fn city(this: Store<Self>, city_id: CityId) -> Result<Field<City>, SimError> {
+        let index = this
+            .read()
+            .cities
+            .iter()
+            .position(|city| city.id == city_id)
+            .ok_or(SimError::CityNotFound(city_id))?;
+        this.cities()
+            .iter_unkeyed()
+            .nth(index)
+            .map(Into::into)
+            .ok_or(SimError::CityNotFound(city_id))
+    }
+
+    /// The city the given person lives in.
+    pub fn city_of(&self, person_id: PersonId) -> Option<CityId> {
+        self.cities
+            .iter()
+            .find(|city| city.population.contains(person_id))
+            .map(|city| city.id)
+    }
+
+    /// Look up a person in whichever city they live in.
+    pub fn find_person(this: Store<Self>, person_id: PersonId) -> Option<Field<Person>> {
+        let city_id = this.read_untracked().city_of(person_id)?;
+        Population::person(
+            Self::city(this, city_id).ok()?.population().into(),
+            person_id,
+        )
+        .ok()
+    }
+
+    /// Move a person to another city, keeping their id and everything attached to them.
+    pub fn migrate_person(
+        this: Store<Self>,
+        person_id: PersonId,
+        from_city: CityId,
+        to_city: CityId,
+    ) -> Result<(), SimError> {
+        if Self::reject_if_read_only(this, "migrate a person") {
+            return Err(SimError::ReadOnly);
+        }
+        let moved = Self::transfer_person(this, person_id, from_city, to_city);
+        Self::check_invariants(this);
+        moved
+    }
+
+    /// Move a person between cities, even in read-only mode.
+    fn transfer_person(
+        this: Store<Self>,
+        person_id: PersonId,
+        from_city: CityId,
+        to_city: CityId,
+    ) -> Result<(), SimError> {
+        if from_city == to_city {
+            return Err(SimError::SameCity(from_city));
+        }
+        if write_during_read(|| format!("cities[{from_city:?}].population.people[{person_id:?}]")) {
+            return Err(SimError::WriteDuringRead);
+        }
+        // Both cities are looked up first, so that nobody is removed without a city to move to.
+        let from = Self::city(this, from_city)?.population();
+        let to = Self::city(this, to_city)?.population();
+        let person =
+            Population::remove_person(from.into(), person_id).ok_or(SimError::NotInCity {
+                person_id,
+                city_id: from_city,
+            })?;
+        Population::add_person(to.into(), person);
+
+        let week = this.week().get_untracked();
+        record_mutation(
+            week,
+            || format!("cities[{from_city:?}].population.people[{person_id:?}]"),
+            MutationKind::Remove,
+            "GlobalState::transfer_person",
+        );
+        record_mutation(
+            week,
+            || format!("cities[{to_city:?}].population.people[{person_id:?}]"),
+            MutationKind::Insert,
+            "GlobalState::transfer_person",
+        );
+        Ok(())
+    }
+
+    /// Remove every modifier of every person, one write per city. Does not touch the trash.
+    pub fn reset_modifiers(this: Store<Self>) {
+        if Self::reject_if_read_only(this, "reset modifiers") {
+            return;
+        }
+        if write_during_read(|| "cities[*].population.people".to_string()) {
+            return;
+        }
+        let week = this.week().get_untracked();
+        let removed: usize = this
+            .cities()
+            .iter_unkeyed()
+            .map(|city| Population::reset_modifiers(city.population().into(), week))
+            .sum();
+        log::info!("Reset modifiers: removed {removed} modifiers");
+        Self::check_invariants(this);
+    }
+
+    /// Set the week back to zero, in one write. People keep their modifiers,
+    /// but their modifier history restarts from the current modifiers.
+    /// Goals are back in progress, as their deadlines count from week zero, and the people
+    /// in the trash count as removed in week zero, so they are kept for the full retention.
+    pub fn reset_week(this: Store<Self>) {
+        if Self::reject_if_read_only(this, "reset the week") {
+            return;
+        }
+        if write_during_read(|| "week".to_string()) {
+            return;
+        }
+        let previous_week = this.week().get_untracked();
+        this.update(|state| {
+            state.week = 0;
+            state.last_week_outcome = None;
+            state.rng_audit_log.clear();
+            state.modifier_stats.clear();
+            for goal in &mut state.goals {
+                goal.status = GoalStatus::InProgress;
+            }
+            for city in &mut state.cities {
+                for person in &mut city.population.people {
+                    let happiness = &mut person.happiness;
+                    happiness.history = ModifierHistory::new(0, &happiness.happiness_modifiers);
+                }
+                for (_, removed_week) in &mut city.population.trash {
+                    *removed_week = 0;
+                }
+            }
+        });
+        record_mutation(
+            0,
+            || "week".to_string(),
+            MutationKind::Update,
+            "GlobalState::reset_week",
+        );
+        log::info!("Reset week: {previous_week} to 0");
+        Self::check_invariants(this);
+    }
+
+    /// Forget what happened so far, in one write: the outcome of the last week, with its
+    /// migrations and alerts, the RNG audit log and the mutation journal.
+    /// The week, the people and the goals stay as they are.
+    pub fn clear_events(this: Store<Self>) {
+        if Self::reject_if_read_only(this, "clear the events") {
+            return;
+        }
+        if write_during_read(|| "last_week_outcome".to_string()) {
+            return;
+        }
+        let mut audited_weeks = 0;
+        this.update(|state| {
+            state.last_week_outcome = None;
+            audited_weeks = state.rng_audit_log.len();
+            state.rng_audit_log.clear();
+        });
+        let journal_records = clear_mutation_journal();
+        record_mutation(
+            this.week().get_untracked(),
+            || "last_week_outcome".to_string(),
+            MutationKind::Remove,
+            "GlobalState::clear_events",
+        );
+        log::info!(
+            "Cleared events: the last week's outcome, {audited_weeks} audited weeks \
+             and {journal_records} journal records"
+        );
+        Self::check_invariants(this);
+    }
+
+    /// Move a person to their city's trash, from where they can be restored
+    /// until [`TrashSystem`] purges them.
+    pub fn trash_person(this: Store<Self>, person_id: PersonId) -> Result<(), SimError> {
+        if Self::reject_if_read_only(this, "remove a person") {
+            return Err(SimError::ReadOnly);
+        }
+        let city_id = this
+            .read_untracked()
+            .city_of(person_id)
+            .ok_or(SimError::PersonNotFound(person_id))?;
+        if write_during_read(|| format!("cities[{city_id:?}].population.people[{person_id:?}]")) {
+            return Err(SimError::WriteDuringRead);
+        }
+        let week = this.week().get_untracked();
+        if !Population::trash_person(
+            Self::city(this, city_id)?.population().into(),
+            person_id,
+            week,
+        ) {
+            return Err(SimError::NotInCity { person_id, city_id });
+        }
+
+        record_mutation(
+            week,
+            || format!("cities[{city_id:?}].population.people[{person_id:?}]"),
+            MutationKind::Remove,
+            "GlobalState::trash_person",
+        );
+        record_mutation(
+            week,
+            || format!("cities[{city_id:?}].population.trash[{person_id:?}]"),
+            MutationKind::Insert,
+            "GlobalState::trash_person",
+        );
+        Self::check_invariants(this);
+        Ok(())
+    }
+
+    /// Move a person from the trash back into the city, with their original id.
+    pub fn restore_person(
+        this: Store<Self>,
+        city_id: CityId,
+        person_id: PersonId,
+    ) -> Result<(), SimError> {
+        if Self::reject_if_read_only(this, "restore a person") {
+            return Err(SimError::ReadOnly);
+        }
+        if write_during_read(|| format!("cities[{city_id:?}].population.trash[{person_id:?}]")) {
+            return Err(SimError::WriteDuringRead);
+        }
+        if !Population::restore_person(Self::city(this, city_id)?.population().into(), person_id) {
+            return Err(SimError::NotInTrash { person_id, city_id });
+        }
+
+        let week = this.week().get_untracked();
+        record_mutation(
+            week,
+            || format!("cities[{city_id:?}].population.trash[{person_id:?}]"),
+            MutationKind::Remove,
+            "GlobalState::restore_person",
+        );
+        record_mutation(
+            week,
+            || format!("cities[{city_id:?}].population.people[{person_id:?}]"),
+            MutationKind::Insert,
+            "GlobalState::restore_person",
+        );
+        Self::check_invariants(this);
+        Ok(())
+    }
+
+    /// Exclude a person from the weekly tick, or include them again.
+    pub fn set_person_paused(this: Store<Self>, person_id: PersonId, paused: bool) {
+        if Self::reject_if_read_only(this, "pause a person") {
+            return;
+        }
+        if write_during_read(|| format!("people[{person_id:?}].paused"))
+            || exceeds_mutation_rate(person_id, "GlobalState::set_person_paused")
+        {
+            return;
+        }
+        let Some(person) = Self::find_person(this, person_id) else {
+            log::warn!("Cannot pause {person_id:?}, they do not exist");
+            return;
+        };
+        person.paused().set(paused);
+        record_mutation(
+            this.week().get_untracked(),
+            || format!("people[{person_id:?}].paused"),
+            MutationKind::Update,
+            "GlobalState::set_person_paused",
+        );
+    }
+
+    pub fn set_person_notes(this: Store<Self>, person_id: PersonId, notes: String) {
+        if Self::reject_if_read_only(this, "edit notes") {
+            return;
+        }
+        if write_during_read(|| format!("people[{person_id:?}].notes"))
+            || exceeds_mutation_rate(person_id, "GlobalState::set_person_notes")
+        {
+            return;
+        }
+        let Some(person) = Self::find_person(this, person_id) else {
+            log::warn!("Cannot edit notes of {person_id:?}, they do not exist");
+            return;
+        };
+        person.notes().set(notes);
+        record_mutation(
+            this.week().get_untracked(),
+            || format!("people[{person_id:?}].notes"),
+            MutationKind::Update,
+            "GlobalState::set_person_notes",
+        );
+    }
+
+    pub fn set_modifier_magnitude(
+        this: Store<Self>,
+        person_id: PersonId,
+        modifier_id: HappinessModifierId,
+        magnitude: f64,
+    ) -> Result<(), MagnitudeError> {
+        if Self::reject_if_read_only(this, "edit a modifier") {
+            return Err(SimError::ReadOnly.into());
+        }
+        if write_during_read(|| {
+            format!(
+                "people[{person_id:?}].happiness.happiness_modifiers[{modifier_id:?}].magnitude"
+            )
+        }) {
+            return Err(SimError::WriteDuringRead.into());
+        }
+        if exceeds_mutation_rate(person_id, "GlobalState::set_modifier_magnitude") {
+            return Err(MagnitudeError::RateLimited);
+        }
+        let person =
+            Self::find_person(this, person_id).ok_or(SimError::PersonNotFound(person_id))?;
+        Happiness::set_modifier_magnitude(person.happiness().into(), modifier_id, magnitude)?;
+        record_mutation(
+            this.week().get_untracked(),
+            || {
+                format!(
+                    "people[{person_id:?}].happiness.happiness_modifiers[{modifier_id:?}].magnitude"
+                )
+            },
+            MutationKind::Update,
+            "GlobalState::set_modifier_magnitude",
+        );
+        Ok(())
+    }
+
+    /// Add a pinned modifier of the given kind to a person,
+    /// optionally overriding the spec's base magnitude.
+    /// Returns the id of the new modifier, or `None` if it was not added.
+    pub fn add_pinned_modifier(
+        this: Store<Self>,
+        person_id: PersonId,
+        spec: ModifierSpecId,
+        magnitude: Option<f64>,
+    ) -> Option<HappinessModifierId> {
+        if Self::reject_if_read_only(this, "add a modifier") {
+            return None;
+        }
+        if write_during_read(|| format!("people[{person_id:?}].happiness.happiness_modifiers"))
+            || exceeds_mutation_rate(person_id, "GlobalState::add_pinned_modifier")
+        {
+            return None;
+        }
+        let Some(person) = Self::find_person(this, person_id) else {
+            log::warn!("Cannot add a modifier to {person_id:?}, they do not exist");
+            return None;
+        };
+        let week = this.week().get_untracked();
+        let modifier_id = Self::with_ids(this, |ids| {
+            Happiness::add_pinned_modifier(person.happiness().into(), spec, magnitude, week, ids)
+        });
+        Self::count_added_modifiers(this, spec, 1);
+        Some(modifier_id)
+    }
+
+    /// Remove a modifier of a person, like undoing [`GlobalState::add_pinned_modifier`].
+    pub fn remove_modifier(
+        this: Store<Self>,
+        person_id: PersonId,
+        modifier_id: HappinessModifierId,
+    ) -> Result<(), SimError> {
+        if Self::reject_if_read_only(this, "remove a modifier") {
+            return Err(SimError::ReadOnly);
+        }
+        if write_during_read(|| {
+            format!("people[{person_id:?}].happiness.happiness_modifiers[{modifier_id:?}]")
+        }) {
+            return Err(SimError::WriteDuringRead);
+        }
+        let person =
+            Self::find_person(this, person_id).ok_or(SimError::PersonNotFound(person_id))?;
+        if !Happiness::remove_modifier(person.happiness().into(), modifier_id) {
+            return Err(SimError::ModifierNotFound(modifier_id));
+        }
+        record_mutation(
+            this.week().get_untracked(),
+            || format!("people[{person_id:?}].happiness.happiness_modifiers[{modifier_id:?}]"),
+            MutationKind::Remove,
+            "GlobalState::remove_modifier",
+        );
+        Ok(())
+    }
+
+    /// Add a modifier to every person in the city for whom `predicate` holds.
+    /// Returns the number of people affected.
+    pub fn apply_modifier_to_matching(
+        this: Store<Self>,
+        city_id: CityId,
+        predicate: impl Fn(&Person) -> bool,
+        template: ModifierTemplate,
+    ) -> Result<usize, SimError> {
+        if Self::reject_if_read_only(this, "add modifiers") {
+            return Err(SimError::ReadOnly);
+        }
+        if write_during_read(|| format!("cities[{city_id:?}].population.people")) {
+            return Err(SimError::WriteDuringRead);
+        }
+        let population = Self::city(this, city_id)?.population();
+        let week = this.week().get_untracked();
+        let count = Self::with_ids(this, |ids| {
+            Population::apply_modifier_to_matching(
+                population.into(),
+                predicate,
+                template,
+                week,
+                ids,
+            )
+        });
+        Self::count_added_modifiers(this, template.spec, count);
+        Ok(count)
+    }
+
+    /// Pin or unpin a modifier of a person.
+    /// Does nothing but log an error if changed too often, see [`exceeds_mutation_rate`].
+    pub fn set_modifier_pinned(
+        this: Store<Self>,
+        person_id: PersonId,
+        modifier_id: HappinessModifierId,
+        pinned: bool,
+    ) -> Result<(), SimError> {
+        if Self::reject_if_read_only(this, "pin a modifier") {
+            return Err(SimError::ReadOnly);
+        }
+        if write_during_read(|| {
+            format!("people[{person_id:?}].happiness.happiness_modifiers[{modifier_id:?}].pinned")
+        }) {
+            return Err(SimError::WriteDuringRead);
+        }
+        if exceeds_mutation_rate(person_id, "GlobalState::set_modifier_pinned") {
+            return Ok(());
+        }
+        let person =
+            Self::find_person(this, person_id).ok_or(SimError::PersonNotFound(person_id))?;
+        if !Happiness::set_modifier_pinned(person.happiness().into(), modifier_id, pinned) {
+            return Err(SimError::ModifierNotFound(modifier_id));
+        }
+        record_mutation(
+            this.week().get_untracked(),
+            || {
+                format!(
+                    "people[{person_id:?}].happiness.happiness_modifiers[{modifier_id:?}].pinned"
+                )
+            },
+            MutationKind::Update,
+            "GlobalState::set_modifier_pinned",
+        );
+        Ok(())
+    }
+
+    /// Move a modifier to the position of another one of the same person.
+    pub fn move_modifier_to(
+        this: Store<Self>,
+        person_id: PersonId,
+        modifier_id: HappinessModifierId,
+        target_id: HappinessModifierId,
+    ) {
+        if modifier_id == target_id || Self::reject_if_read_only(this, "reorder modifiers") {
+            return;
+        }
+        if write_during_read(|| format!("people[{person_id:?}].happiness.happiness_modifiers"))
+            || exceeds_mutation_rate(person_id, "GlobalState::move_modifier_to")
+        {
+            return;
+        }
+        let Some(person) = Self::find_person(this, person_id) else {
+            log::warn!("Cannot reorder the modifiers of {person_id:?}, they do not exist");
+            return;
+        };
+        let happiness = person.happiness();
+        let Some(new_index) = happiness
+            .happiness_modifiers()
+            .read_untracked()
+            .iter()
+            .position(|modifier| modifier.id == target_id)
+        else {
+            log::warn!("Cannot move {modifier_id:?} to {target_id:?}, it no longer exists");
+            return;
+        };
+        if !Happiness::move_modifier(happiness.into(), modifier_id, new_index) {
+            log::warn!("Cannot move {modifier_id:?}, it no longer exists");
+            return;
+        }
+        record_mutation(
+            this.week().get_untracked(),
+            || format!("people[{person_id:?}].happiness.happiness_modifiers"),
+            MutationKind::Update,
+            "GlobalState::move_modifier_to",
+        );
+    }
+
+    /// Log a warning and return true if the state is read-only.
+    fn reject_if_read_only(this: Store<Self>, action: &str) -> bool {
+        let read_only = this.read_only().get_untracked();
+        if read_only {
+            log::warn!("Refusing to {action} in read-only mode");
+        }
+        read_only
+    }
+
+    /// Check the invariants that only hold by convention.
+    pub fn validate(&self) -> Vec<InvariantViolation> {
+        let mut violations = Vec::new();
+        let mut city_ids = HashSet::new();
+        let mut cities_by_person = HashMap::new();
+
+        for city in &self.cities {
+            if !city_ids.insert(city.id) {
+                violations.push(InvariantViolation::DuplicateCityId(city.id));
+            }
+            violations.extend(city.population.validate(&self.ids));
+
+            for person in &city.population.people {
+                match cities_by_person.insert(person.id, city.id) {
+                    Some(other_city) if other_city != city.id => {
+                        violations.push(InvariantViolation::PersonInSeveralCities {
+                            person_id: person.id,
+                            cities: (other_city, city.id),
+                        })
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Validate the state in debug builds, logging and storing any violations.
+    pub fn check_invariants(this: Store<Self>) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let violations = this.read_untracked().validate();
+        for violation in &violations {
+            log::error!("Invariant violated: {violation}");
+        }
+        this.invariant_violations().set(violations);
+    }
+}
+
+/// Tuning values of the simulation.
+#[derive(Debug, Clone, Store)]
+pub struct SimConfig {
+    /// The fraction of its magnitude a decaying modifier loses each week.
+    modifier_decay_rate: f64,
+    /// Decaying modifiers are removed once their magnitude drops below this.
+    modifier_decay_threshold: f64,
+    /// How much lower a city's average happiness must be than the happiest city's
+    /// before its people consider moving.
+    migration_happiness_gap: f64,
+    /// The weekly probability that a person in an unhappy city moves.
+    migration_probability: f64,
+    /// The number of weeks a removed person can be restored before being purged.
+    trash_retention_weeks: u64,
+    /// Names of the [`TickSystem`]s that are skipped when a week finishes.
+    disabled_systems: BTreeSet<&'static str>,
+    /// The modifiers every new person starts with.
+    initial_modifiers: Vec<InitialModifier>,
+    /// The lowest total happiness of each [`HappinessBand`] above [`HappinessBand::Miserable`].
+    unhappy_threshold: f64,
+    content_threshold: f64,
+    happy_threshold: f64,
+    thriving_threshold: f64,
+}
+
+/// A modifier new people start with, see [`SimConfig`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InitialModifier {
+    pub spec: ModifierSpecId,
+    /// Overrides the base magnitude of the spec.
+    pub magnitude: Option<f64>,
+    /// Overrides the default duration of the spec, in weeks.
+    pub duration: Option<u32>,
+}
+
+impl InitialModifier {
+    pub fn instantiate(self, week: u64, ids: &mut IdAllocator) -> HappinessModifier {
+        let mut modifier = HappinessModifier::create(self.spec, week, ids);
+        modifier.magnitude = self.magnitude;
+        if self.duration.is_some() {
+            modifier.remaining_weeks = self.duration;
+        }
+        modifier
+    }
+
+    /// Written as `spec:magnitude:weeks`, with `_` for the defaults of the spec.
+    /// When parsing, the trailing parts may be left out.
+    fn to_storage_string(self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "_".to_string());
+        format!(
+            "{}:{}:{}",
+            self.spec.0,
+            optional(self.magnitude.map(|magnitude| magnitude.to_string())),
+            optional(self.duration.map(|duration| duration.to_string())),
+        )
+    }
+
+    /// Parse an entry written by [`InitialModifier::to_storage_string`].
+    /// Rejects unknown specs and magnitudes outside the spec's range.
+    fn from_storage_string(text: &str) -> Option<Self> {
+        fn optional<T: FromStr>(part: Option<&str>) -> Option<Option<T>> {
+            match part {
+                None | Some("_") => Some(None),
+                Some(part) => part.parse().ok().map(Some),
+            }
+        }
+
+        let mut parts = text.split(':');
+        let spec = ModifierSpecId(parts.next()?.parse().ok()?);
+        let magnitude: Option<f64> = optional(parts.next())?;
+        let duration = optional(parts.next())?;
+        if parts.next().is_some() {
+            return None;
+        }
+        let spec_info = spec.spec()?;
+        if magnitude.is_some_and(|magnitude| {
+            !(spec_info.min_magnitude..=spec_info.max_magnitude).contains(&magnitude)
+        }) {
+            return None;
+        }
+        Some(Self {
+            spec,
+            magnitude,
+            duration,
+        })
+    }
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            modifier_decay_rate: 0.1,
+            modifier_decay_threshold: 0.01,
+            migration_happiness_gap: 0.1,
+            migration_probability: 0.05,
+            trash_retention_weeks: 4,
+            disabled_systems: BTreeSet::new(),
+            initial_modifiers: vec![InitialModifier {
+                spec: ModifierSpecId::DEFAULT,
+                magnitude: None,
+                duration: None,
+            }],
+            unhappy_threshold: 0.2,
+            content_threshold: 0.4,
+            happy_threshold: 0.6,
+            thriving_threshold: 0.8,
+        }
+    }
+}
+
+/// Saved as its storage string, so that saves and localStorage share one format.
+impl Serialize for SimConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_storage_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for SimConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|text| Self::from_storage_string(&text))
+    }
+}
+
+/// The localStorage key of the config. Kept apart from the game state, so starting over keeps it.
+const SIM_CONFIG_STORAGE_KEY: &str = "sim_config";
+
+impl SimConfig {
+    /// Load the config from localStorage, or the defaults if none was saved.
+    pub fn load() -> Self {
+        let saved = window()
+            .local_storage()
+            .ok()
+            .flatten()
+            .and_then(|storage| storage.get_item(SIM_CONFIG_STORAGE_KEY).ok().flatten());
+        saved.map_or_else(Self::default, |text| Self::from_storage_string(&text))
+    }
+
+    pub fn save(&self) {
+        let result = window().local_storage().and_then(|storage| {
+            storage
+                .ok_or_else(|| JsValue::from_str("localStorage is unavailable"))?
+                .set_item(SIM_CONFIG_STORAGE_KEY, &self.to_storage_string())
+        });
+        if let Err(error) = result {
+            log::warn!("Could not save the config: {error:?}");
+        }
+    }
+
+    /// One `name=value` line per field.
+    pub fn to_storage_string(&self) -> String {
+        let disabled_systems: Vec<_> = self.disabled_systems.iter().copied().collect();
+        let initial_modifiers: Vec<_> = self
+            .initial_modifiers
+            .iter()
+            .map(|initial| initial.to_storage_string())
+            .collect();
+        format!(
+            "modifier_decay_rate={}\n\
+             modifier_decay_threshold={}\n\
+             migration_happiness_gap={}\n\
+             migration_probability={}\n\
+             trash_retention_weeks={}\n\
+             disabled_systems={}\n\
+             initial_modifiers={}\n\
+             unhappy_threshold={}\n\
+             content_threshold={}\n\
+             happy_threshold={}\n\
+             thriving_threshold={}\n",
+            self.modifier_decay_rate,
+            self.modifier_decay_threshold,
+            self.migration_happiness_gap,
+            self.migration_probability,
+            self.trash_retention_weeks,
+            disabled_systems.join(","),
+            initial_modifiers.join(","),
+            self.unhappy_threshold,
+            self.content_threshold,
+            self.happy_threshold,
+            self.thriving_threshold,
+        )
+    }
+
+    /// The config as RON, for sharing outside the app.
+    pub fn to_ron(&self) -> String {
+        let float = |value: f64| format!("{value:?}");
+        let optional = |value: Option<String>| {
+            value.map_or("None".to_string(), |value| format!("Some({value})"))
+        };
+        let disabled_systems: Vec<_> = self
+            .disabled_systems
+            .iter()
+            .map(|name| format!("{name:?}"))
+            .collect();
+        let initial_modifiers: Vec<_> = self
+            .initial_modifiers
+            .iter()
+            .map(|initial| {
+                format!(
+                    "        (spec: {}, magnitude: {}, duration: {}),\n",
+                    initial.spec.0,
+                    optional(initial.magnitude.map(float)),
+                    optional(initial.duration.map(|duration| duration.to_string())),
+                )
+            })
+            .collect();
+        format!(
+            "SimConfig(\n    \
+             modifier_decay_rate: {},\n    \
+             modifier_decay_threshold: {},\n    \
+             migration_happiness_gap: {},\n    \
+             migration_probability: {},\n    \
+             trash_retention_weeks: {},\n    \
+             disabled_systems: [{}],\n    \
+             initial_modifiers: [\n{}    ],\n    \
+             unhappy_threshold: {},\n    \
+             content_threshold: {},\n    \
+             happy_threshold: {},\n    \
+             thriving_threshold: {},\n\
+             )\n",
+            float(self.modifier_decay_rate),
+            float(self.modifier_decay_threshold),
+            float(self.migration_happiness_gap),
+            float(self.migration_probability),
+            self.trash_retention_weeks,
+            disabled_systems.join(", "),
+            initial_modifiers.concat(),
+            float(self.unhappy_threshold),
+            float(self.content_threshold),
+            float(self.happy_threshold),
+            float(self.thriving_threshold),
+        )
+    }
+
+    /// Parse a config written by [`SimConfig::to_storage_string`].
+    /// Missing fields and invalid values fall back to their defaults, invalid ones with a warning.
+    pub fn from_storage_string(text: &str) -> Self {
+        let values: HashMap<_, _> = text
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .collect();
+        let defaults = Self::default();
+        let disabled_systems = values.get("disabled_systems").map(|names| {
+            names
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .filter_map(|name| {
+                    let system = TICK_SYSTEMS.iter().find(|system| system.name() == name);
+                    if system.is_none() {
+                        log::warn!("Ignoring unknown tick system {name:?} in the saved config");
+                    }
+                    system.map(|system| system.name())
+                })
+                .collect()
+        });
+        let initial_modifiers = values.get("initial_modifiers").map(|entries| {
+            entries
+                .split(',')
+                .filter(|entry| !entry.is_empty())
+                .filter_map(|entry| {
+                    let initial = InitialModifier::from_storage_string(entry);
+                    if initial.is_none() {
+                        log::warn!(
+                            "Ignoring invalid initial modifier {entry:?} in the saved config"
+                        );
+                    }
+                    initial
+                })
+                .collect()
+        });
+
+        Self {
+            modifier_decay_rate: config_value(
+                &values,
+                "modifier_decay_rate",
+                defaults.modifier_decay_rate,
+                is_fraction,
+            ),
+            modifier_decay_threshold: config_value(
+                &values,
+                "modifier_decay_threshold",
+                defaults.modifier_decay_threshold,
+                is_non_negative,
+            ),
+            migration_happiness_gap: config_value(
+                &values,
+                "migration_happiness_gap",
+                defaults.migration_happiness_gap,
+                is_non_negative,
+            ),
+            migration_probability: config_value(
+                &values,
+                "migration_probability",
+                defaults.migration_probability,
+                is_fraction,
+            ),
+            trash_retention_weeks: config_value(
+                &values,
+                "trash_retention_weeks",
+                defaults.trash_retention_weeks,
+                |_| true,
+            ),
+            disabled_systems: disabled_systems.unwrap_or(defaults.disabled_systems),
+            initial_modifiers: initial_modifiers.unwrap_or(defaults.initial_modifiers),
+            unhappy_threshold: config_value(
+                &values,
+                "unhappy_threshold",
+                defaults.unhappy_threshold,
+                is_finite,
+            ),
+            content_threshold: config_value(
+                &values,
+                "content_threshold",
+                defaults.content_threshold,
+                is_finite,
+            ),
+            happy_threshold: config_value(
+                &values,
+                "happy_threshold",
+                defaults.happy_threshold,
+                is_finite,
+            ),
+            thriving_threshold: config_value(
+                &values,
+                "thriving_threshold",
+                defaults.thriving_threshold,
+                is_finite,
+            ),
+        }
+    }
+}
+
+/// The parsed value of a config field, or the default if it is missing or invalid.
+fn config_value<T: FromStr>(
+    values: &HashMap<&str, &str>,
+    name: &str,
+    default: T,
+    is_valid: fn(&T) -> bool,
+) -> T {
+    let Some(text) = values.get(name) else {
+        return default;
+    };
+    match text.parse() {
+        Ok(value) if is_valid(&value) => value,
+        _ => {
+            log::warn!("Ignoring invalid config value {name}={text}, using the default");
+            default
+        }
+    }
+}
+
+fn is_fraction(value: &f64) -> bool {
+    (0.0..=1.0).contains(value)
+}
+
+fn is_non_negative(value: &f64) -> bool {
+    value.is_finite() && *value >= 0.0
+}
+
+fn is_finite(value: &f64) -> bool {
+    value.is_finite()
+}
+
+/// A small deterministic random number generator (SplitMix64),
+/// so that runs with the same seed behave the same on every platform.
+///
+/// Every draw names its call site with an [`RngLabel`]. In audit mode, the draws per label
+/// are counted, so that an extra draw that desynchronizes two runs can be found.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimRng {
+    state: u64,
+    /// The draws of the current week, or `None` if not auditing. Not saved, so loading stops auditing.
+    #[serde(skip)]
+    draws: Option<RngDrawCounts>,
+}
+
+/// Names the call site of an RNG draw. Only the constants below exist.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RngLabel(&'static str);
+
+impl RngLabel {
+    pub const MIGRATION: Self = Self("migration");
+}
+
+/// The number of RNG draws per [`RngLabel`] in one week.
+pub type RngDrawCounts = BTreeMap<&'static str, u64>;
+
+/// The first week and label whose draw counts differ between two audit logs.
+/// Only weeks present in both logs are compared.
+pub fn first_rng_divergence(
+    a: &[(u64, RngDrawCounts)],
+    b: &[(u64, RngDrawCounts)],
+) -> Option<(u64, &'static str)> {
+    let b: BTreeMap<_, _> = b.iter().map(|(week, draws)| (*week, draws)).collect();
+    a.iter().find_map(|(week, a_draws)| {
+        let b_draws = b.get(week)?;
+        a_draws
+            .keys()
+            .chain(b_draws.keys())
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .find(|label| a_draws.get(label) != b_draws.get(label))
+            .map(|label| (*week, label))
+    })
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            draws: None,
+        }
+    }
+
+    pub fn set_audit(&mut self, enabled: bool) {
+        self.draws = enabled.then(RngDrawCounts::new);
+    }
+
+    /// The draws counted since the last call, or `None` if not auditing.
+    pub fn take_draw_counts(&mut self) -> Option<RngDrawCounts> {
+        self.draws.as_mut().map(std::mem::take)
+    }
+
+    pub fn next_u64(&mut self, label: RngLabel) -> u64 {
+        if let Some(draws) = &mut self.draws {
+            *draws.entry(label.0).or_default() += 1;
+        }
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed value in `[0, 1)`.
+    pub fn next_f64(&mut self, label: RngLabel) -> f64 {
+        (self.next_u64(label) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// One step of finishing a week. Systems run in the order of [`TICK_SYSTEMS`].
+///
+/// A system only touches the state through its [`TickContext`],
+/// so it can be run on its own against a constructed context.
+pub trait TickSystem: Sync {
+    /// The name used to disable the system in [`SimConfig`].
+    fn name(&self) -> &'static str;
+
+    fn run(&self, ctx: &mut TickContext);
+}
+
+/// Everything a [`TickSystem`] may read or write while a week finishes.
+pub struct TickContext<'a> {
+    pub state: Store<GlobalState>,
+    pub config: &'a SimConfig,
+    /// Written back to the state after all systems ran.
+    pub rng: &'a mut SimRng,
+    /// Written back to the state after all systems ran, like the RNG.
+    pub ids: &'a mut IdAllocator,
+    /// The week being finished. The week counter is already incremented.
+    pub week: u64,
+    pub outcome: &'a mut WeekOutcome,
+}
+
+/// The systems run by each finished week, in order:
+///
+/// 1. [`ModifierSystem`] expires, decays and adds happiness modifiers.
+/// 2. [`MigrationSystem`] moves people based on the happiness left by step 1.
+/// 3. [`TrashSystem`] purges people removed too long ago.
+pub static TICK_SYSTEMS: &[&dyn TickSystem] = &[&ModifierSystem, &MigrationSystem, &TrashSystem];
+
+/// Ages the happiness modifiers of everyone in every city.
+pub struct ModifierSystem;
+
+impl TickSystem for ModifierSystem {
+    fn name(&self) -> &'static str {
+        "modifiers"
+    }
+
+    fn run(&self, ctx: &mut TickContext) {
+        for city in ctx.state.cities().iter_unkeyed() {
+            Population::finish_week(
+                city.population().into(),
+                ctx.week,
+                ctx.config,
+                &mut ctx.outcome.modifier_stats,
+                ctx.ids,
+            );
+        }
+    }
+}
+
+/// People in a city whose average happiness is far below the happiest city's
+/// may move there, each with the configured probability.
+pub struct MigrationSystem;
+
+impl TickSystem for MigrationSystem {
+    fn name(&self) -> &'static str {
+        "migration"
+    }
+
+    fn run(&self, ctx: &mut TickContext) {
+        let averages: Vec<_> = ctx
+            .state
+            .cities()
+            .iter_unkeyed()
+            .filter_map(|city| {
+                Population::average_happiness(city.population().into())
+                    .map(|average| (city.id().get_untracked(), average))
+            })
+            .collect();
+        let Some(&(happiest_city, highest_average)) = averages
+            .iter()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(CmpOrdering::Equal))
+        else {
+            return;
+        };
+
+        // Decide on all migrations before moving anyone, so that moves don't affect the averages.
+        let mut migrations = Vec::new();
+        for &(city_id, average) in &averages {
+            if highest_average - average < ctx.config.migration_happiness_gap {
+                continue;
+            }
+            let city = match GlobalState::city(ctx.state, city_id) {
+                Ok(city) => city.read_untracked(),
+                Err(error) => {
+                    log::warn!("No migrations from {city_id:?}: {error}");
+                    continue;
+                }
+            };
+            let people = city.population.people_in_id_order();
+            for person in people.filter(|person| !person.paused) {
+                if ctx.rng.next_f64(RngLabel::MIGRATION) < ctx.config.migration_probability {
+                    migrations.push(Migration {
+                        person_id: person.id,
+                        from: city_id,
+                        to: happiest_city,
+                    });
+                }
+            }
+        }
+
+        for migration in migrations {
+            let Migration {
+                person_id,
+                from,
+                to,
+            } = migration;
+            log::info!("{person_id:?} migrates from {from:?} to {to:?}");
+            match GlobalState::transfer_person(ctx.state, person_id, from, to) {
+                Ok(()) => ctx.outcome.migrations.push(migration),
+                Err(error) => log::warn!("Migration of {person_id:?} failed: {error}"),
+            }
+        }
+    }
+}
+
+/// Permanently drops people who were removed more than
+/// [`SimConfig`]'s retention period ago.
+pub struct TrashSystem;
+
+impl TickSystem for TrashSystem {
+    fn name(&self) -> &'static str {
+        "trash"
+    }
+
+    fn run(&self, ctx: &mut TickContext) {
+        let oldest_kept_week = ctx.week.saturating_sub(ctx.config.trash_retention_weeks);
+        for city in ctx.state.cities().iter_unkeyed() {
+            let purged = Population::purge_trash(city.population().into(), oldest_kept_week);
+            ctx.outcome.purged_people.extend(purged);
+        }
+    }
+}
+
+/// What happened while a week finished, collected by the [`TickSystem`]s.
+#[derive(Debug, Clone)]
+pub struct WeekOutcome {
+    pub week: u64,
+    pub migrations: Vec<Migration>,
+    /// People dropped from the trash for good.
+    pub purged_people: Vec<PersonId>,
+    /// The average happiness of everyone after all systems ran.
+    pub average_happiness: Option<f64>,
+    /// Goals that were completed or failed this week.
+    pub finished_goals: Vec<Goal>,
+    pub watcher_alerts: Vec<WatcherAlert>,
+    /// The RNG draws of the week, if the RNG was audited.
+    pub rng_draws: Option<RngDrawCounts>,
+    pub modifier_stats: ModifierStats,
+}
+
+/// The number of weeks of [`GlobalState`]'s modifier stats that are kept.
+pub const MODIFIER_STATS_WEEKS: usize = 52;
+
+/// The modifier counts of one week per kind.
+pub type ModifierStats = BTreeMap<ModifierSpecId, ModifierKindStats>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModifierKindStats {
+    /// Added during the week, by the tick or by hand since the previous week.
+    pub added: u32,
+    /// Removed by the tick because they expired or decayed away.
+    pub expired: u32,
+    /// Present after the tick.
+    pub active: u32,
+    /// The sum of the current magnitudes of the active modifiers.
+    pub magnitude_sum: f64,
+}
+
+impl ModifierKindStats {
+    /// The average magnitude of the active modifiers, or `None` if there are none.
+    pub fn average_magnitude(&self) -> Option<f64> {
+        (self.active > 0).then(|| self.magnitude_sum / self.active as f64)
+    }
+}
+
+impl WeekOutcome {
+    pub fn new(week: u64) -> Self {
+        Self {
+            week,
+            migrations: Vec::new(),
+            purged_people: Vec::new(),
+            average_happiness: None,
+            finished_goals: Vec::new(),
+            watcher_alerts: Vec::new(),
+            rng_draws: None,
+            modifier_stats: ModifierStats::new(),
+        }
+    }
+
+    /// A short summary for screen readers, e.g.
+    /// "Week 12 finished: 1 person moved, average happiness 64%."
+    pub fn announcement(&self) -> String {
+        let mut details = Vec::new();
+        match self.migrations.len() {
+            0 => {}
+            1 => details.push("1 person moved".to_string()),
+            count => details.push(format!("{count} people moved")),
+        }
+        if !self.purged_people.is_empty() {
+            details.push(format!(
+                "{} purged from the trash",
+                self.purged_people.len()
+            ));
+        }
+        if let Some(average_happiness) = self.average_happiness {
+            details.push(format!(
+                "average happiness {:.0}%",
+                average_happiness * 100.0
+            ));
+        }
+
+        let mut announcement = format!("Week {} finished", self.week);
+        if !details.is_empty() {
+            announcement.push_str(": ");
+            announcement.push_str(&details.join(", "));
+        }
+        announcement.push('.');
+        for goal in &self.finished_goals {
+            let verb = match goal.status {
+                GoalStatus::Failed => "failed",
+                _ => "completed",
+            };
+            announcement.push_str(&format!(" Goal {verb}: {}.", goal.kind));
+        }
+        for alert in &self.watcher_alerts {
+            announcement.push_str(&format!(
+                " Person {} is at {:.0}% happiness.",
+                alert.person_id,
+                alert.total * 100.0
+            ));
+        }
+        announcement
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub person_id: PersonId,
+    pub from: CityId,
+    pub to: CityId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub kind: GoalKind,
+    pub status: GoalStatus,
+}
+
+impl Goal {
+    pub fn new(kind: GoalKind) -> Self {
+        Self {
+            kind,
+            status: GoalStatus::InProgress,
+        }
+    }
+
+    pub fn default_goals() -> Vec<Self> {
+        vec![
+            // Above the base magnitude of the default modifier, where the average stays
+            // if nobody adds a modifier by hand.
+            Self::new(GoalKind::ReachAverageHappiness {
+                target: 0.75,
+                deadline_week: 52,
+            }),
+            Self::new(GoalKind::KeepPopulation {
+                min: 5,
+                until_week: 52,
+            }),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum GoalKind {
+    /// The average happiness of everyone must reach `target` by `deadline_week`.
+    ReachAverageHappiness { target: f64, deadline_week: u64 },
+    /// The total population must not drop below `min` until `until_week`.
+    KeepPopulation { min: usize, until_week: u64 },
+}
+
+impl GoalKind {
+    /// The status of the goal after the given week.
+    pub fn evaluate(
+        self,
+        week: u64,
+        population: usize,
+        average_happiness: Option<f64>,
+    ) -> GoalStatus {
+        match self {
+            Self::ReachAverageHappiness {
+                target,
+                deadline_week,
+            } => {
+                if average_happiness.is_some_and(|average| average >= target) {
+                    GoalStatus::Completed
+                } else if week >= deadline_week {
+                    GoalStatus::Failed
+                } else {
+                    GoalStatus::InProgress
+                }
+            }
+            Self::KeepPopulation { min, until_week } => {
+                if population < min {
+                    GoalStatus::Failed
+                } else if week >= until_week {
+                    GoalStatus::Completed
+                } else {
+                    GoalStatus::InProgress
+                }
+            }
+        }
+    }
+
+    /// How far along the goal is, between zero and one.
+    pub fn progress(self, week: u64, average_happiness: Option<f64>) -> f64 {
+        let progress = match self {
+            Self::ReachAverageHappiness { target, .. } => average_happiness.unwrap_or(0.0) / target,
+            Self::KeepPopulation { until_week, .. } => week as f64 / until_week as f64,
+        };
+        progress.clamp(0.0, 1.0)
+    }
+}
+
+impl fmt::Display for GoalKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReachAverageHappiness {
+                target,
+                deadline_week,
+            } => write!(
+                f,
+                "Reach an average happiness of {:.0}% by week {deadline_week}",
+                target * 100.0
+            ),
+            Self::KeepPopulation { min, until_week } => {
+                write!(f, "Keep at least {min} people until week {until_week}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GoalStatus {
+    InProgress,
+    Completed,
+    Failed,
+}
+
+impl GoalStatus {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::InProgress => "In progress",
+            Self::Completed => "Completed",
+            Self::Failed => "Failed",
+        }
+    }
+
+    /// The `data-status` attribute of the goal, for styling.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::InProgress => "in-progress",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Alerts when people's total happiness crosses a threshold.
+///
+/// A watcher fires once per crossing. After firing for a person, it stays quiet for them
+/// until their happiness recovers past the threshold by more than `hysteresis`.
+/// People already past the threshold when the watcher is added fire on the next tick.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watcher {
+    pub id: WatcherId,
+    pub threshold: f64,
+    pub direction: CrossingDirection,
+    pub scope: WatcherScope,
+    pub hysteresis: f64,
+    /// The people this watcher fired for who have not recovered yet.
+    fired_for: BTreeSet<PersonId>,
+}
+
+impl Watcher {
+    /// Update the watcher with a person's new total. Returns true if it fires.
+    fn observe(&mut self, person_id: PersonId, total: f64) -> bool {
+        if let WatcherScope::Person(watched) = self.scope {
+            if watched != person_id {
+                return false;
+            }
+        }
+        let (crossed, recovered) = match self.direction {
+            CrossingDirection::Below => (
+                total < self.threshold,
+                total >= self.threshold + self.hysteresis,
+            ),
+            CrossingDirection::Above => (
+                total > self.threshold,
+                total <= self.threshold - self.hysteresis,
+            ),
+        };
+        if self.fired_for.contains(&person_id) {
+            if recovered {
+                self.fired_for.remove(&person_id);
+            }
+            false
+        } else if crossed {
+            self.fired_for.insert(person_id);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl fmt::Display for Watcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let who = match self.scope {
+            WatcherScope::All => "Anyone".to_string(),
+            WatcherScope::Person(person_id) => format!("Person {person_id}"),
+        };
+        let direction = match self.direction {
+            CrossingDirection::Below => "below",
+            CrossingDirection::Above => "above",
+        };
+        write!(
+            f,
+            "{who} crossing {direction} {:.0}% (hysteresis {:.0}%)",
+            self.threshold * 100.0,
+            self.hysteresis * 100.0
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct WatcherId(u64);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CrossingDirection {
+    Below,
+    Above,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum WatcherScope {
+    All,
+    Person(PersonId),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WatcherAlert {
+    pub watcher_id: WatcherId,
+    pub person_id: PersonId,
+    pub total: f64,
+}
+
+/// The number of weeks a fast-forward finishes before yielding to the browser.
+pub const FAST_FORWARD_CHUNK_WEEKS: u64 = 10;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FastForward {
+    progress: Progress,
+    /// Set when the user asked to stop. The fast-forward stops after the current chunk.
+    cancel_requested: bool,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Progress {
+    pub done: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum InvariantViolation {
+    IndexOutOfBounds {
+        person_id: PersonId,
+        index: usize,
+    },
+    IndexPointsToWrongPerson {
+        person_id: PersonId,
+        index: usize,
+        actual_person_id: PersonId,
+    },
+    PersonNotIndexed {
+        person_id: PersonId,
+        index: usize,
+    },
+    DuplicatePersonId(PersonId),
+    DuplicateCityId(CityId),
+    PersonInSeveralCities {
+        person_id: PersonId,
+        cities: (CityId, CityId),
+    },
+    DuplicateHappinessModifierId(HappinessModifierId),
+    UnknownModifierSpec {
+        modifier_id: HappinessModifierId,
+        spec_id: ModifierSpecId,
+    },
+    PersonIdAllocatorBehind {
+        next: u64,
+        existing: PersonId,
+    },
+    HappinessModifierIdAllocatorBehind {
+        next: u64,
+        existing: HappinessModifierId,
+    },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IndexOutOfBounds { person_id, index } => write!(
+                f,
+                "index entry for {person_id:?} points to {index}, which is out of bounds"
+            ),
+            Self::IndexPointsToWrongPerson {
+                person_id,
+                index,
+                actual_person_id,
+            } => write!(
+                f,
+                "index entry for {person_id:?} points to {index}, which holds {actual_person_id:?}"
+            ),
+            Self::PersonNotIndexed { person_id, index } => {
+                write!(f, "{person_id:?} at {index} has no matching index entry")
+            }
+            Self::DuplicatePersonId(person_id) => write!(f, "{person_id:?} is used more than once"),
+            Self::DuplicateCityId(city_id) => write!(f, "{city_id:?} is used more than once"),
+            Self::PersonInSeveralCities {
+                person_id,
+                cities: (first, second),
+            } => write!(f, "{person_id:?} lives in both {first:?} and {second:?}"),
+            Self::DuplicateHappinessModifierId(modifier_id) => {
+                write!(f, "{modifier_id:?} is used more than once")
+            }
+            Self::UnknownModifierSpec {
+                modifier_id,
+                spec_id,
+            } => write!(f, "{modifier_id:?} refers to unknown {spec_id:?}"),
+            Self::PersonIdAllocatorBehind { next, existing } => {
+                write!(
+                    f,
+                    "next person id {next} does not exceed existing {existing:?}"
+                )
+            }
+            Self::HappinessModifierIdAllocatorBehind { next, existing } => write!(
+                f,
+                "next happiness modifier id {next} does not exceed existing {existing:?}"
+            ),
+        }
+    }
+}
+
+/// Mount the app to the page body. Called by the binary.
+pub fn start() {
+    // Get better error messages from WASM in the browser.
+    console_error_panic_hook::set_once();
+    // Init logging in the browser console.
+    console_log::init_with_level(Level::Debug).unwrap();
+
+    mount_to_body(App)
+}
+
+#[component]
+fn App() -> impl IntoView {
+    let read_only = ReadOnly(query_flag("spectate"));
+    provide_context(Store::new(UiPreferences {
+        modifier_filter: ModifierFilter::from_query(),
+        ..UiPreferences::default()
+    }));
+    provide_context(read_only);
+
+    view! { <GameRoot /> }
+}
+
+/// The number of the current game, counting from zero. See [`GameRoot`].
+#[derive(Debug, Clone, Copy)]
+pub struct GameGeneration {
+    generation: RwSignal<u64>,
+    /// The game to continue instead of starting a new one, taken by the next game.
+    loaded: StoredValue<Option<GlobalState>>,
+}
+
+impl GameGeneration {
+    pub fn new() -> Self {
+        Self {
+            generation: RwSignal::new(0),
+            loaded: StoredValue::new(None),
+        }
+    }
+
+    /// Drop the current game and start a fresh one.
+    pub fn start_new_game(self) {
+        self.generation.update(|generation| *generation += 1);
+    }
+
+    /// Drop the current game and continue `state`, e.g. one read by [`GlobalState::from_save`].
+    pub fn load_game(self, state: GlobalState) {
+        self.loaded.set_value(Some(state));
+        self.start_new_game();
+    }
+
+    /// The state and selection of the next game: the loaded one if there is one,
+    /// otherwise a new one.
+    fn next_game(self, read_only: ReadOnly) -> (GlobalState, (CityId, Option<PersonId>)) {
+        match self.loaded.try_update_value(Option::take).flatten() {
+            Some(state) => loaded_game(state, read_only),
+            None => new_game(SimConfig::load(), read_only),
+        }
+    }
+}
+
+impl Default for GameGeneration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Owns the store of the current game and everything rendered from it.
+///
+/// Each game is rendered under its own reactive owner. Starting a new game disposes it,
+/// which drops the old store and runs the cleanups of the old components, stopping their
+/// intervals and listeners. A fast-forward still running stops at its next chunk.
+#[component]
+fn GameRoot() -> impl IntoView {
+    let read_only = expect_context::<ReadOnly>();
+    let generation = GameGeneration::new();
+    provide_context(generation);
+
+    move || {
+        generation.generation.track();
+        let (global_state, selection) = generation.next_game(read_only);
+        under_game_owner(selection, move || {
+            view! {
+                <AppState state=global_state>
+                    <Game />
+                    <ToastHost />
+                    <ContextMenuHost />
+                    <DialogHost />
+                </AppState>
+            }
+        })
+    }
+}
+
+/// The state of a new game and the city and person selected at its start.
+fn new_game(config: SimConfig, read_only: ReadOnly) -> (GlobalState, (CityId, Option<PersonId>)) {
+    let mut global_state = GlobalState::new_debug_instance(config);
+    global_state.read_only = read_only.0;
+    // Every game counts its ids from 1, so each one starts with the person of the
+    // bug reproduction selected.
+    (global_state, (CityId(1), Some(PersonId(2))))
+}
+
+/// A loaded game and the city selected when it continues: the first one, without a person.
+fn loaded_game(
+    mut global_state: GlobalState,
+    read_only: ReadOnly,
+) -> (GlobalState, (CityId, Option<PersonId>)) {
+    // Read-only mode is not saved, see [`GlobalState::to_json`].
+    global_state.read_only = read_only.0;
+    let city = global_state
+        .cities
+        .first()
+        .map_or(CityId(1), |city| city.id);
+    (global_state, (city, None))
+}
+
+/// Run `render` under a fresh owner that provides the per-game contexts.
+///
+/// The returned view holds the only strong reference to the owner, so dropping it
+/// disposes everything `render` created.
+fn under_game_owner<V>(
+    (city, person): (CityId, Option<PersonId>),
+    render: impl FnOnce() -> V,
+) -> OwnedView<V> {
+    let owner = Owner::new();
+    let view = owner.with(|| {
+        provide_context(HoveredEntity(RwSignal::new(None)));
+        provide_context(Selection {
+            city: RwSignal::new(city),
+            person: RwSignal::new(person),
+        });
+        provide_dialogs();
+        provide_toasts();
+        provide_context_menu();
+        render()
+    });
+    OwnedView::new_with_owner(view, owner)
+}
+
+/// Starts a new game after asking, as the current one cannot be restored.
+#[component]
+fn NewGameButton() -> impl IntoView {
+    let generation = expect_context::<GameGeneration>();
+    let new_game = move |_| {
+        open_dialog(DialogRequest::Confirm {
+            title: "New game".to_string(),
+            message: "Drop the current game and start a new one?".to_string(),
+            confirm_label: "New game",
+            on_choice: Callback::new(move |confirmed: bool| {
+                if confirmed {
+                    generation.start_new_game();
+                }
+            }),
+        });
+    };
+
+    view! { <button on:click=new_game>"New game"</button> }
+}
+
+/// The localStorage key of the saved game. There is one save, which each save replaces.
+const SAVED_GAME_STORAGE_KEY: &str = "saved_game";
+
+fn store_saved_game(json: &str) -> Result<(), JsValue> {
+    window()
+        .local_storage()?
+        .ok_or_else(|| JsValue::from_str("localStorage is unavailable"))?
+        .set_item(SAVED_GAME_STORAGE_KEY, json)
+}
+
+fn saved_game() -> Option<String> {
+    window()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(SAVED_GAME_STORAGE_KEY).ok().flatten())
+}
+
+/// Saves the game to localStorage and continues the saved one, through
+/// [`GlobalState::to_json`] and [`GlobalState::from_save`].
+/// Loading keeps the current config unless the saved one is chosen.
+#[component]
+fn SaveLoadButtons() -> impl IntoView {
+    let state = use_global_state();
+    let generation = expect_context::<GameGeneration>();
+    let announcement = expect_context::<LiveAnnouncement>();
+    let use_saved_config = RwSignal::new(false);
+
+    let save = move |_| {
+        let json = state.read_untracked().to_json();
+        match store_saved_game(&json) {
+            Ok(()) => announcement.0.set(format!(
+                "Saved the game at week {}.",
+                state.week().get_untracked()
+            )),
+            Err(error) => {
+                log::warn!("Could not save the game: {error:?}");
+                announcement.0.set("Could not save the game.".to_string());
+            }
+        }
+    };
+    let load = move |_| {
+        let Some(json) = saved_game() else {
+            announcement.0.set("There is no saved game.".to_string());
+            return;
+        };
+        let config = state.config().get_untracked();
+        match GlobalState::from_save(&json, config, use_saved_config.get_untracked()) {
+            Ok(loaded) => {
+                open_dialog(DialogRequest::Confirm {
+                    title: "Load game".to_string(),
+                    message: "Drop the current game and continue the saved one?".to_string(),
+                    confirm_label: "Load",
+                    on_choice: Callback::new(move |confirmed: bool| {
+                        if confirmed {
+                            generation.load_game(loaded.clone());
+                        }
+                    }),
+                });
+            }
+            Err(error) => {
+                announcement
+                    .0
+                    .set(format!("Could not read the saved game: {error}"));
+            }
+        }
+    };
+
+    view! {
+        <button on:click=save>"Save game"</button>
+        <button on:click=load>"Load game"</button>
+        <label>
+            <input
+                type="checkbox"
+                prop:checked=use_saved_config
+                on:change=move |event| use_saved_config.set(event_target_checked(&event))
+            />
+            " Use the saved config"
+        </label>
+    }
+}
+
+/// The whole page. Rendered inside [`AppState`], so everything below it shares one store.
+#[component]
+fn Game() -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let preferences = expect_context::<Store<UiPreferences>>();
+    let selection = expect_context::<Selection>();
+    let announcement = LiveAnnouncement(RwSignal::new(String::new()));
+    provide_context(announcement);
+
+    GlobalState::check_invariants(state);
+    sync_modifier_filter_query(preferences);
+    // Changes take effect on the next tick, which reads the config afresh.
+    Effect::new(move || state.config().read().save());
+    // Announce each finished week. A running fast-forward is only announced once it stops.
+    Effect::new(move || {
+        if state.fast_forward().read().is_some() {
+            return;
+        }
+        if let Some(outcome) = state.last_week_outcome().read().as_ref() {
+            announcement.0.set(outcome.announcement());
+        }
+    });
+
+    if read_only.0 {
+        // In spectator mode, the simulation drives itself.
+        let handle = set_interval_with_handle(
+            move || {
+                // Hidden tabs are caught up when they return, and a catch-up is already advancing.
+                let is_idle =
+                    document().hidden() || state.fast_forward().read_untracked().is_some();
+                if !is_idle && !state.read_untracked().is_game_over() {
+                    GlobalState::advance_week(state);
+                }
+            },
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        on_cleanup(move || handle.clear());
+        sync_background_auto_advance(state, preferences, announcement);
+    }
+
+    // The game over dialog stays open for as long as the game is over.
+    let is_game_over = Memo::new(move |_| state.read().is_game_over());
+    let game_over_dialog = StoredValue::new(None::<DialogHandle>);
+    Effect::new(move || {
+        if is_game_over.get() {
+            if game_over_dialog.read_value().is_none() {
+                game_over_dialog.set_value(Some(open_dialog(DialogRequest::GameOver)));
+            }
+        } else if let Some(handle) = game_over_dialog.write_value().take() {
+            handle.close();
+        }
+    });
+
+    view! {
+        <header>
+            <ThemeToggle />
+            <Show when=move || read_only.0>
+                <BackgroundAdvanceToggle />
+            </Show>
+            <Show when=move || !read_only.0>
+                <NewGameButton />
+                <SaveLoadButtons />
+            </Show>
+        </header>
+        <LiveRegion />
+        <InvariantViolationBanner />
+        <GoalsPanel />
+        <Show when=move || !read_only.0>
+            <button on:click=move |_| GlobalState::finish_week(state)>Finish Week</button>
+            <FastForwardControls />
+        </Show>
+        <LastWeekSummary />
+        <CitySwitcher />
+        {move || {
+            let city_id = selection.city.get();
+            view! {
+                <ErrorBoundary fallback=show_errors>
+                    <PopulationSummary city_id />
+                    <PopulationTable
+                        city_id
+                        selected=selection.person
+                        on_select=Callback::new(move |person_id| selection.person.set(Some(person_id)))
+                    />
+                    <BulkModifierForm city_id />
+                    <TrashPanel city_id />
+                </ErrorBoundary>
+            }
+        }}
+        {move || {
+            let city_id = selection.city.get();
+            // The selection may briefly point to a person in another city while switching.
+            selection
+                .person
+                .get()
+                .filter(|&person_id| state.read().city_of(person_id) == Some(city_id))
+                .map(|person_id| view! { <PersonView city_id person_id /> })
+        }}
+        <WatchersPanel />
+        <ForkPanel />
+        <DebugPanel />
+    }
+}
+
+/// Lists the watchers with the alerts of the last week, and adds new ones.
+#[component]
+fn WatchersPanel() -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let threshold = RwSignal::new(30.0);
+    let direction = RwSignal::new(CrossingDirection::Below);
+    let person = RwSignal::new(String::new());
+    let hysteresis = RwSignal::new(5.0);
+    let error = RwSignal::new(None::<String>);
+
+    let add = move |_| {
+        let person = person.get_untracked();
+        let scope = if person.trim().is_empty() {
+            WatcherScope::All
+        } else {
+            match person.trim().parse() {
+                Ok(person_id) => WatcherScope::Person(person_id),
+                Err(parse_error) => {
+                    error.set(Some(format!("Invalid person: {parse_error}")));
+                    return;
+                }
+            }
+        };
+        error.set(None);
+        GlobalState::add_watcher(
+            state,
+            threshold.get_untracked() / 100.0,
+            direction.get_untracked(),
+            scope,
+            hysteresis.get_untracked() / 100.0,
+        );
+    };
+    let watchers = move || {
+        let alerts = state
+            .last_week_outcome()
+            .read()
+            .as_ref()
+            .map(|outcome| outcome.watcher_alerts.clone())
+            .unwrap_or_default();
+        state
+            .watchers()
+            .read()
+            .iter()
+            .map(|watcher| {
+                let watcher_id = watcher.id;
+                let alerts = alerts
+                    .iter()
+                    .filter(|alert| alert.watcher_id == watcher_id)
+                    .map(|alert| {
+                        view! {
+                            <li>
+                                <PersonLink person_id=alert.person_id />
+                                {format!(" is at {:.0}%", alert.total * 100.0)}
+                            </li>
+                        }
+                    })
+                    .collect_view();
+                view! {
+                    <li>
+                        {watcher.to_string()} " "
+                        <button
+                            disabled=read_only.0
+                            on:click=move |_| GlobalState::remove_watcher(state, watcher_id)
+                        >
+                            Delete
+                        </button>
+                        <ul>{alerts}</ul>
+                    </li>
+                }
+            })
+            .collect_view()
+    };
+    let number_input = |value: RwSignal<f64>| {
+        view! {
+            <input
+                type="number"
+                prop:value=move || value.get().to_string()
+                on:change=move |event| {
+                    if let Ok(parsed) = event_target_value(&event).parse() {
+                        value.set(parsed);
+                    }
+                }
+            />
+        }
+    };
+
+    view! {
+        <details>
+            <summary>Watchers</summary>
+            <ul>{watchers}</ul>
+            <Show when=move || !read_only.0>
+                <select on:change=move |event| {
+                    direction
+                        .set(
+                            if event_target_value(&event) == "above" {
+                                CrossingDirection::Above
+                            } else {
+                                CrossingDirection::Below
+                            },
+                        )
+                }>
+                    <option value="below">Below</option>
+                    <option value="above">Above</option>
+                </select>
+                {number_input(threshold)}
+                "% for person "
+                <input
+                    placeholder="anyone"
+                    prop:value=move || person.get()
+                    on:input=move |event| person.set(event_target_value(&event))
+                />
+                " with hysteresis "
+                {number_input(hysteresis)}
+                "% "
+                <button on:click=add>Add watcher</button>
+                {move || error.get().map(|error| view! { <span class=styles::ERROR>{error}</span> })}
+            </Show>
+        </details>
+    }
+}
+
+/// Creates or discards a fork of the state and compares it with the original
+/// after fast-forwarding both.
+#[component]
+fn ForkPanel() -> impl IntoView {
+    let state = use_global_state();
+    let fork = use_forked_state();
+    let read_only = expect_context::<ReadOnly>();
+    let weeks = RwSignal::new(10u64);
+    let create_fork = move |_| {
+        let forked = Store::new(state.read_untracked().fork());
+        if let Some(previous) = fork.get_untracked() {
+            previous.dispose();
+        }
+        fork.set(Some(forked));
+    };
+    let discard_fork = move |_| {
+        if let Some(forked) = fork.get_untracked() {
+            forked.dispose();
+        }
+        fork.set(None);
+    };
+    let run_both = move |_| {
+        let weeks = weeks.get_untracked();
+        GlobalState::finish_weeks_async(state, weeks);
+        if let Some(forked) = fork.get_untracked() {
+            GlobalState::finish_weeks_async(forked, weeks);
+        }
+    };
+    let stats = |store: Store<GlobalState>| {
+        let (population, average_happiness) = GlobalState::overall_happiness(store);
+        let average_happiness = average_happiness
+            .map(|happiness| format!("{:.0}%", happiness * 100.0))
+            .unwrap_or_else(|| "—".to_string());
+        (store.week().get(), population, average_happiness)
+    };
+    let comparison = move || {
+        fork.get().map(|forked| {
+            let (week, population, average_happiness) = stats(state);
+            let (fork_week, fork_population, fork_average_happiness) = stats(forked);
+            view! {
+                <table>
+                    <tr>
+                        <th></th>
+                        <th>Original</th>
+                        <th>Fork</th>
+                    </tr>
+                    <tr>
+                        <th>Week</th>
+                        <td>{week}</td>
+                        <td>{fork_week}</td>
+                    </tr>
+                    <tr>
+                        <th>Population</th>
+                        <td>{population}</td>
+                        <td>{fork_population}</td>
+                    </tr>
+                    <tr>
+                        <th>Average happiness</th>
+                        <td>{average_happiness}</td>
+                        <td>{fork_average_happiness}</td>
+                    </tr>
+                </table>
+                <input
+                    type="number"
+                    min="1"
+                    prop:value=move || weeks.get().to_string()
+                    on:change=move |event| {
+                        if let Ok(value) = event_target_value(&event).parse() {
+                            weeks.set(value);
+                        }
+                    }
+                />
+                <button on:click=run_both>"Run both"</button>
+                <button on:click=discard_fork>"Discard fork"</button>
+            }
+        })
+    };
+
+    view! {
+        <Show when=move || !read_only.0>
+            <details>
+                <summary>Fork</summary>
+                <button on:click=create_fork>
+                    {move || if fork.read().is_some() { "Fork again" } else { "Fork" }}
+                </button>
+                {comparison}
+            </details>
+        </Show>
+    }
+}
+
+#[component]
+fn DebugPanel() -> impl IntoView {
+    view! {
+        <details>
+            <summary>Debug</summary>
+            <SettingsPanel />
+            <TickSystemToggles />
+            <IconGallery />
+            <MutationJournalView />
+            <MutationRateLimitInput />
+            <ModifierStatsPanel />
+            <RngAuditPanel />
+            <ResetPanel />
+        </details>
+    }
+}
+
+/// Resets single parts of the state, leaving the rest alone.
+#[component]
+fn ResetPanel() -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    // Resets cannot be undone, so they ask first.
+    let confirm = move |title: &str, message: &str, reset: fn(Store<GlobalState>)| {
+        open_dialog(DialogRequest::Confirm {
+            title: title.to_string(),
+            message: message.to_string(),
+            confirm_label: "Reset",
+            on_choice: Callback::new(move |confirmed: bool| {
+                if confirmed {
+                    reset(state);
+                }
+            }),
+        });
+    };
+
+    view! {
+        <Show when=move || !read_only.0>
+            <fieldset>
+                <legend>Reset</legend>
+                <button on:click=move |_| {
+                    confirm(
+                        "Clear all modifiers",
+                        "Remove every modifier of every person? This cannot be undone.",
+                        |state| {
+                            GlobalState::reset_modifiers(state);
+                        },
+                    )
+                }>"Clear all modifiers"</button>
+                <button on:click=move |_| {
+                    confirm(
+                        "Reset the week",
+                        "Go back to week 0, clear the history and restart the goals? This cannot be undone.",
+                        GlobalState::reset_week,
+                    )
+                }>"Reset the week"</button>
+                <button on:click=move |_| {
+                    confirm(
+                        "Clear the events",
+                        "Forget the last week's outcome, the RNG audit log and the mutation journal? This cannot be undone.",
+                        GlobalState::clear_events,
+                    )
+                }>"Clear the events"</button>
+            </fieldset>
+        </Show>
+    }
+}
+
+/// The modifier counts of the last finished week per kind, with the active count over time.
+#[component]
+fn ModifierStatsPanel() -> impl IntoView {
+    const WIDTH: f64 = 80.0;
+    const HEIGHT: f64 = 16.0;
+
+    let state = use_global_state();
+    let history = state.modifier_stats();
+    let rows = move || {
+        let history = history.read();
+        let Some((week, latest)) = history.back() else {
+            return view! {
+                <tr>
+                    <td colspan="6">"No week finished yet"</td>
+                </tr>
+            }
+            .into_any();
+        };
+        MODIFIER_SPECS
+            .iter()
+            .map(|spec| {
+                let stats = latest.get(&spec.id).copied().unwrap_or_default();
+                let active: Vec<_> = history
+                    .iter()
+                    .map(|(_, stats)| stats.get(&spec.id).map_or(0, |stats| stats.active))
+                    .collect();
+                let high = active.iter().copied().max().unwrap_or(0).max(1) as f64;
+                let step = WIDTH / (MODIFIER_STATS_WEEKS - 1) as f64;
+                let points = active
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &count)| {
+                        let x = index as f64 * step;
+                        let y = HEIGHT - count as f64 / high * HEIGHT;
+                        format!("{x:.1},{y:.1}")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                view! {
+                    <tr>
+                        <td>{spec.name}</td>
+                        <td>{stats.active}</td>
+                        <td>
+                            {stats
+                                .average_magnitude()
+                                .map_or("—".to_string(), |magnitude| {
+                                    format!("{:.0}%", magnitude * 100.0)
+                                })}
+                        </td>
+                        <td>{stats.added}</td>
+                        <td>{stats.expired}</td>
+                        <td>
+                            <svg
+                                width=WIDTH
+                                height=HEIGHT
+                                role="img"
+                                aria-label=format!("Active {} modifiers until week {week}", spec.name)
+                            >
+                                <polyline points=points fill="none" stroke="currentColor" />
+                            </svg>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect_view()
+            .into_any()
+    };
+
+    view! {
+        <h3>Modifier stats</h3>
+        <table>
+            <thead>
+                <tr>
+                    <th>Kind</th>
+                    <th>Active</th>
+                    <th>Average magnitude</th>
+                    <th>Added</th>
+                    <th>Expired</th>
+                    <th>Active over time</th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
+
+/// Turns RNG auditing on and off, and exports the draws per week as `week,label,draws` lines.
+/// With a fork, also shows where the draws of the two runs first differ.
+#[component]
+fn RngAuditPanel() -> impl IntoView {
+    let state = use_global_state();
+    let fork = use_forked_state();
+    let auditing = move || state.rng().read().draws.is_some();
+    let export = move || {
+        state
+            .rng_audit_log()
+            .read()
+            .iter()
+            .flat_map(|(week, draws)| {
+                draws
+                    .iter()
+                    .map(move |(label, count)| format!("{week},{label},{count}\n"))
+            })
+            .collect::<String>()
+    };
+    let divergence = move || {
+        fork.get().map(|forked| {
+            let log = state.rng_audit_log().read();
+            let fork_log = forked.rng_audit_log().read();
+            match first_rng_divergence(&log, &fork_log) {
+                Some((week, label)) => format!("The fork first diverges in week {week} at {label}"),
+                None => "The fork draws the same so far".to_string(),
+            }
+        })
+    };
+
+    view! {
+        <fieldset>
+            <legend>RNG audit</legend>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=auditing
+                    on:change=move |event| {
+                        GlobalState::set_rng_audit(state, event_target_checked(&event))
+                    }
+                />
+                " Count draws"
+            </label>
+            <Show when=auditing>
+                <textarea readonly rows="6" prop:value=export />
+                <p>{divergence}</p>
+            </Show>
+        </fieldset>
+    }
+}
+
+/// Inputs for the tuning values in [`SimConfig`].
+#[component]
+fn SettingsPanel() -> impl IntoView {
+    let state = use_global_state();
+    let config = state.config();
+    let announcement = expect_context::<LiveAnnouncement>().0;
+
+    view! {
+        <fieldset>
+            <legend>Settings</legend>
+            <ConfigInput
+                label="Modifier decay rate"
+                value=config.modifier_decay_rate()
+                is_valid=is_fraction
+            />
+            <ConfigInput
+                label="Modifier decay threshold"
+                value=config.modifier_decay_threshold()
+                is_valid=is_non_negative
+            />
+            <ConfigInput
+                label="Migration happiness gap"
+                value=config.migration_happiness_gap()
+                is_valid=is_non_negative
+            />
+            <ConfigInput
+                label="Migration probability"
+                value=config.migration_probability()
+                is_valid=is_fraction
+            />
+            <ConfigInput
+                label="Trash retention weeks"
+                value=config.trash_retention_weeks()
+                is_valid=|_| true
+            />
+            <ConfigInput
+                label="Unhappy from happiness"
+                value=config.unhappy_threshold()
+                is_valid=is_finite
+            />
+            <ConfigInput
+                label="Content from happiness"
+                value=config.content_threshold()
+                is_valid=is_finite
+            />
+            <ConfigInput
+                label="Happy from happiness"
+                value=config.happy_threshold()
+                is_valid=is_finite
+            />
+            <ConfigInput
+                label="Thriving from happiness"
+                value=config.thriving_threshold()
+                is_valid=is_finite
+            />
+            <button on:click=move |_| config.set(SimConfig::default())>"Reset to defaults"</button>
+            <button on:click=move |_| copy_to_clipboard(config.read_untracked().to_ron(), announcement)>
+                "Copy as RON"
+            </button>
+        </fieldset>
+    }
+}
+
+/// Put `text` on the clipboard, announcing whether it worked.
+fn copy_to_clipboard(text: String, announcement: RwSignal<String>) {
+    let promise = window().navigator().clipboard().write_text(&text);
+    leptos::task::spawn_local(async move {
+        match wasm_bindgen_futures::JsFuture::from(promise).await {
+            Ok(_) => announcement.set("Copied to the clipboard".to_string()),
+            Err(error) => {
+                log::warn!("Could not copy to the clipboard: {error:?}");
+                announcement.set("Could not copy to the clipboard".to_string());
+            }
+        }
+    });
+}
+
+/// A text input for one config value. Invalid input is shown as an error and not applied.
+#[component]
+fn ConfigInput<T>(
+    label: &'static str,
+    #[prop(into)] value: Field<T>,
+    is_valid: fn(&T) -> bool,
+) -> impl IntoView
+where
+    T: FromStr + ToString + Send + Sync + 'static,
+{
+    let error = RwSignal::new(false);
+    let apply = move |event| match event_target_value(&event).trim().parse() {
+        Ok(parsed) if is_valid(&parsed) => {
+            value.set(parsed);
+            error.set(false);
+        }
+        _ => error.set(true),
+    };
+
+    view! {
+        <label>
+            {label} " "
+            <input prop:value=move || value.read().to_string() on:change=apply />
+            <Show when=move || error.get()>
+                <span class=styles::ERROR>" invalid"</span>
+            </Show>
+        </label>
+        <br />
+    }
+}
+
+/// Checkboxes to enable or disable each [`TickSystem`].
+#[component]
+fn TickSystemToggles() -> impl IntoView {
+    let state = use_global_state();
+    let disabled_systems = state.config().disabled_systems();
+
+    view! {
+        <fieldset>
+            <legend>Tick systems</legend>
+            {TICK_SYSTEMS
+                .iter()
+                .map(|system| {
+                    let name = system.name();
+                    view! {
+                        <label>
+                            <input
+                                type="checkbox"
+                                prop:checked=move || !disabled_systems.read().contains(name)
+                                on:change=move |event| {
+                                    if event_target_checked(&event) {
+                                        disabled_systems.write().remove(name);
+                                    } else {
+                                        disabled_systems.write().insert(name);
+                                    }
+                                }
+                            />
+                            {name}
+                        </label>
+                    }
+                })
+                .collect_view()}
+        </fieldset>
+    }
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static READ_SCOPE_DEPTH: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
+}
+
+/// Marks code that reads the store to render, such as derived signals.
+/// While a scope is alive, the write helpers refuse to write in debug builds,
+/// logging the path instead of panicking on a borrow conflict inside the store.
+/// Does nothing in release builds.
+pub struct ReadScope {
+    _private: (),
+}
+
+impl ReadScope {
+    pub fn enter() -> Self {
+        #[cfg(debug_assertions)]
+        READ_SCOPE_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        Self { _private: () }
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for ReadScope {
+    fn drop(&mut self) {
+        READ_SCOPE_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Returns true and logs an error if the store is written inside a [`ReadScope`].
+/// The path is only built when the write is rejected.
+#[cfg(debug_assertions)]
+fn write_during_read(path: impl FnOnce() -> String) -> bool {
+    let reading = READ_SCOPE_DEPTH.with(|depth| depth.get() > 0);
+    if reading {
+        log::error!(
+            "Refusing to write `{}` while reading the store to render",
+            path()
+        );
+    }
+    reading
+}
+
+#[cfg(not(debug_assertions))]
+fn write_during_read(_path: impl FnOnce() -> String) -> bool {
+    false
+}
+
+/// The initial limit of mutations per person per flush, see [`exceeds_mutation_rate`].
+/// Far above what edits or a fast-forward chunk need, far below what hangs the tab.
+#[cfg(debug_assertions)]
+pub const DEFAULT_MUTATION_RATE_LIMIT: u32 = 500;
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static MUTATION_RATE_LIMIT: ArcRwSignal<u32> = ArcRwSignal::new(DEFAULT_MUTATION_RATE_LIMIT);
+    /// The mutations per person since the counts were last reset.
+    static MUTATION_COUNTS: std::cell::RefCell<HashMap<PersonId, u32>> = Default::default();
+}
+
+/// Returns true and logs an error if the person was mutated more often than the limit
+/// in the current flush, which points to an effect that writes what it reads.
+///
+/// The counts are reset in a microtask. The reactive work queued in a flush runs
+/// without yielding to the microtask queue, so a feedback loop never gets that far.
+#[cfg(debug_assertions)]
+fn exceeds_mutation_rate(person_id: PersonId, origin: &'static str) -> bool {
+    let limit = MUTATION_RATE_LIMIT.with(|limit| limit.get_untracked());
+    let count = MUTATION_COUNTS.with(|counts| {
+        let mut counts = counts.borrow_mut();
+        if counts.is_empty() {
+            schedule_mutation_count_reset();
+        }
+        let count = counts.entry(person_id).or_default();
+        *count = count.saturating_add(1);
+        *count
+    });
+    // Logged once per flush, the rejected mutations that follow would flood the console.
+    if count == limit.saturating_add(1) {
+        log::error!(
+            "Refusing more than {limit} mutations of {person_id:?} in one flush, \
+             the last from {origin}. Is an effect writing what it reads?"
+        );
+    }
+    count > limit
+}
+
+#[cfg(all(debug_assertions, target_arch = "wasm32"))]
+fn schedule_mutation_count_reset() {
+    queue_microtask(reset_mutation_counts);
+}
+
+/// Without a browser there is no microtask queue. Callers like [`sim::Simulation`]
+/// reset the counts with [`reset_mutation_counts`] instead.
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+fn schedule_mutation_count_reset() {}
+
+/// Start counting mutations for [`exceeds_mutation_rate`] afresh.
+fn reset_mutation_counts() {
+    #[cfg(debug_assertions)]
+    MUTATION_COUNTS.with(|counts| counts.borrow_mut().clear());
+}
+
+#[cfg(not(debug_assertions))]
+fn exceeds_mutation_rate(_person_id: PersonId, _origin: &'static str) -> bool {
+    false
+}
+
+#[cfg(debug_assertions)]
+#[component]
+fn MutationRateLimitInput() -> impl IntoView {
+    let limit = MUTATION_RATE_LIMIT.with(|limit| limit.clone());
+
+    view! {
+        <label>
+            "Mutations per person per flush: "
+            <input
+                type="number"
+                min="1"
+                prop:value={
+                    let limit = limit.clone();
+                    move || limit.get().to_string()
+                }
+                on:change=move |event| {
+                    if let Ok(value) = event_target_value(&event).trim().parse::<u32>() {
+                        limit.set(value.max(1));
+                    }
+                }
+            />
+        </label>
+    }
+}
+
+#[cfg(not(debug_assertions))]
+#[component]
+fn MutationRateLimitInput() -> impl IntoView {
+    view! { <p>The mutation rate limit requires a debug build.</p> }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MutationKind {
+    Insert,
+    Update,
+    Remove,
+}
+
+/// A logical mutation performed by one of the simulation's write helpers.
+#[cfg(any(debug_assertions, feature = "metrics"))]
+#[derive(Debug, Clone)]
+pub struct MutationRecord {
+    pub week: u64,
+    /// The store path that was written, e.g. `people[PersonId(2)].notes`.
+    /// Person ids are unique across cities, so paths below a person omit the city.
+    pub path: String,
+    pub kind: MutationKind,
+    /// The write helper that performed the mutation.
+    pub origin: &'static str,
+}
+
+/// The maximum number of records kept. Older records are dropped first.
+#[cfg(any(debug_assertions, feature = "metrics"))]
+pub const MUTATION_JOURNAL_CAPACITY: usize = 1000;
+
+/// An opt-in record of the mutations performed through the write helpers.
+/// Only compiled in debug builds or with the `metrics` feature.
+#[cfg(any(debug_assertions, feature = "metrics"))]
+#[derive(Default)]
+struct MutationJournal {
+    enabled: ArcRwSignal<bool>,
+    records: ArcRwSignal<std::collections::VecDeque<MutationRecord>>,
+}
+
+#[cfg(any(debug_assertions, feature = "metrics"))]
+thread_local! {
+    static MUTATION_JOURNAL: MutationJournal = MutationJournal::default();
+}
+
+/// Record a mutation in the journal if it is enabled.
+/// The path is only built when the record is kept.
+#[cfg(any(debug_assertions, feature = "metrics"))]
+fn record_mutation(
+    week: u64,
+    path: impl FnOnce() -> String,
+    kind: MutationKind,
+    origin: &'static str,
+) {
+    MUTATION_JOURNAL.with(|journal| {
+        if !journal.enabled.get_untracked() {
+            return;
+        }
+
+        journal.records.update(|records| {
+            if records.len() >= MUTATION_JOURNAL_CAPACITY {
+                records.pop_front();
+            }
+            records.push_back(MutationRecord {
+                week,
+                path: path(),
+                kind,
+                origin,
+            });
+        });
+    });
+}
+
+/// Drop every record of the journal. Returns the number dropped.
+#[cfg(any(debug_assertions, feature = "metrics"))]
+fn clear_mutation_journal() -> usize {
+    MUTATION_JOURNAL.with(|journal| {
+        let count = journal.records.read_untracked().len();
+        journal.records.write().clear();
+        count
+    })
+}
+
+#[cfg(not(any(debug_assertions, feature = "metrics")))]
+fn clear_mutation_journal() -> usize {
+    0
+}
+
+#[cfg(not(any(debug_assertions, feature = "metrics")))]
+#[inline(always)]
+fn record_mutation(
+    _week: u64,
+    _path: impl FnOnce() -> String,
+    _kind: MutationKind,
+    _origin: &'static str,
+) {
+}
+
+#[cfg(any(debug_assertions, feature = "metrics"))]
+#[component]
+fn MutationJournalView() -> impl IntoView {
+    let (enabled, records) =
+        MUTATION_JOURNAL.with(|journal| (journal.enabled.clone(), journal.records.clone()));
+    let filter = RwSignal::new(String::new());
+    let filtered_records = {
+        let records = records.clone();
+        move || {
+            let filter = filter.get();
+            records
+                .read()
+                .iter()
+                .rev()
+                .filter(|record| record.path.contains(&filter) || record.origin.contains(&filter))
+                .map(|record| {
+                    view! {
+                        <tr>
+                            <td>{record.week}</td>
+                            <td>{format!("{:?}", record.kind)}</td>
+                            <td>{record.path.clone()}</td>
+                            <td>{record.origin}</td>
+                        </tr>
+                    }
+                })
+                .collect_view()
+        }
+    };
+
+    view! {
+        <h3>Mutation journal</h3>
+        <label>
+            <input
+                type="checkbox"
+                prop:checked={
+                    let enabled = enabled.clone();
+                    move || enabled.get()
+                }
+                on:change=move |event| enabled.set(event_target_checked(&event))
+            />
+            " Record mutations"
+        </label>
+        <input
+            placeholder="Filter by path or origin"
+            prop:value=filter
+            on:input=move |event| filter.set(event_target_value(&event))
+        />
+        <button on:click=move |_| records.write().clear()>Clear</button>
+        <table>
+            <thead>
+                <tr>
+                    <th>Week</th>
+                    <th>Kind</th>
+                    <th>Path</th>
+                    <th>Origin</th>
+                </tr>
+            </thead>
+            <tbody>{filtered_records}</tbody>
+        </table>
+    }
+}
+
+#[cfg(not(any(debug_assertions, feature = "metrics")))]
+#[component]
+fn MutationJournalView() -> impl IntoView {
+    view! { <p>The mutation journal requires a debug build or the metrics feature.</p> }
+}
+
+#[component]
+fn FastForwardControls() -> impl IntoView {
+    let state = use_global_state();
+    let weeks = RwSignal::new(100u64);
+    let progress = move || {
+        state
+            .fast_forward()
+            .get()
+            .map(|fast_forward| fast_forward.progress)
+    };
+
+    view! {
+        <input
+            type="number"
+            min="1"
+            prop:value=move || weeks.get().to_string()
+            on:change=move |event| {
+                if let Ok(value) = event_target_value(&event).parse() {
+                    weeks.set(value);
+                }
+            }
+        />
+        <button
+            disabled=move || progress().is_some()
+            on:click=move |_| {
+                GlobalState::finish_weeks_async(state, weeks.get_untracked());
+            }
+        >
+            Fast-forward
+        </button>
+        {move || {
+            progress()
+                .map(|Progress { done, total }| {
+                    view! {
+                        <progress max=total.to_string() value=done.to_string() />
+                        {format!(" {done}/{total} ")}
+                        <button on:click=move |_| GlobalState::cancel_fast_forward(state)>
+                            Cancel
+                        </button>
+                    }
+                })
+        }}
+    }
+}
+
+/// The city and person the user is looking at.
+#[derive(Debug, Clone, Copy)]
+pub struct Selection {
+    pub city: RwSignal<CityId>,
+    pub person: RwSignal<Option<PersonId>>,
+}
+
+#[derive(Debug, Clone, Store, Serialize, Deserialize)]
+pub struct City {
+    id: CityId,
+    name: String,
+    population: Population,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct CityId(u64);
+
+impl City {
+    pub fn new(id: CityId, name: &str, config: &SimConfig, ids: &mut IdAllocator) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            population: Population::new(&config.initial_modifiers, ids),
+        }
+    }
+}
+
+impl Entity for City {
+    type Key = CityId;
+
+    fn key(&self) -> CityId {
+        self.id
+    }
+
+    fn entity_ref(key: CityId) -> EntityRef {
+        EntityRef::City(key)
+    }
+}
+
+/// The latest message for screen readers, shown in the [`LiveRegion`].
+#[derive(Debug, Clone, Copy)]
+pub struct LiveAnnouncement(pub RwSignal<String>);
+
+/// A visually hidden region whose changes screen readers announce politely.
+#[component]
+fn LiveRegion() -> impl IntoView {
+    let announcement = expect_context::<LiveAnnouncement>().0;
+
+    view! {
+        <div class=styles::VISUALLY_HIDDEN role="status" aria-live="polite">
+            {move || announcement.get()}
+        </div>
+    }
+}
+
+/// The name of a person, selecting them when clicked and highlighting them elsewhere when hovered.
+#[component]
+fn PersonLink(person_id: PersonId) -> impl IntoView {
+    let state = use_global_state();
+    let selection = expect_context::<Selection>();
+    let hovered = expect_context::<HoveredEntity>();
+    let entity = EntityRef::Person(person_id);
+    let (hover, unhover) = hovered.track(entity);
+    let select = move || {
+        if let Some(city_id) = state.read_untracked().city_of(person_id) {
+            selection.city.set(city_id);
+            selection.person.set(Some(person_id));
+        }
+    };
+
+    view! {
+        <a
+            href="#"
+            class={styles::PERSON_LINK}
+            {..styles::toggle(styles::PERSON_LINK_HOVERED, move || hovered.0.get() == Some(entity))}
+            on:pointerenter=hover
+            on:pointerleave=unhover
+            on:click=move |event| {
+                event.prevent_default();
+                select();
+            }
+        >
+            {format!("Person {person_id}")}
+        </a>
+    }
+}
+
+/// Lists what happened in the most recently finished week.
+#[component]
+fn LastWeekSummary() -> impl IntoView {
+    let state = use_global_state();
+    let city_name = move |city_id| {
+        GlobalState::city(state, city_id)
+            .map_or_else(|_| format!("{city_id:?}"), |city| city.name().get())
+    };
+
+    move || {
+        state.last_week_outcome().get().map(|outcome| {
+            let migrations = outcome
+                .migrations
+                .iter()
+                .map(|migration| {
+                    let text = format!(
+                        " moved from {} to {}",
+                        city_name(migration.from),
+                        city_name(migration.to)
+                    );
+                    view! {
+                        <li>
+                            <PersonLink person_id=migration.person_id />
+                            {text}
+                        </li>
+                    }
+                })
+                .collect_view();
+            view! {
+                <p class=styles::WEEK_SUMMARY>
+                    {format!(
+                        "Week {}: {} people moved, {} purged from the trash.",
+                        outcome.week,
+                        outcome.migrations.len(),
+                        outcome.purged_people.len(),
+                    )}
+                </p>
+                <ul>{migrations}</ul>
+            }
+        })
+    }
+}
+
+/// Selects the city whose people are shown, and selects the first person living there.
+#[component]
+fn CitySwitcher() -> impl IntoView {
+    let state = use_global_state();
+    let selection = expect_context::<Selection>();
+    let options = move || {
+        state
+            .cities()
+            .iter_unkeyed()
+            .map(|city| {
+                let city_id = city.id().get();
+                view! {
+                    <option value=city_id.0.to_string() selected=selection.city.get() == city_id>
+                        {city.name().get()}
+                    </option>
+                }
+            })
+            .collect_view()
+    };
+    let switch_city = move |event| {
+        let Ok(id) = event_target_value(&event).parse() else {
+            return;
+        };
+        let city_id = CityId(id);
+        let city = match GlobalState::city(state, city_id) {
+            Ok(city) => city,
+            Err(error) => {
+                log::warn!("Cannot switch city: {error}");
+                return;
+            }
+        };
+        let first_person = city
+            .read_untracked()
+            .population
+            .people
+            .first()
+            .map(|person| person.id);
+        selection.city.set(city_id);
+        selection.person.set(first_person);
+    };
+
+    view! {
+        <label>
+            "City: " <select on:change=switch_city>{options}</select>
+        </label>
+    }
+}
+
+/// Whether the app is in read-only spectator mode, where all mutating controls are hidden.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOnly(pub bool);
+
+/// Whether the page was opened with the given query parameter set to `1`.
+fn query_flag(name: &str) -> bool {
+    query_value(name).as_deref() == Some("1")
+}
+
+/// The value of the given URL query parameter, if present.
+fn query_value(name: &str) -> Option<String> {
+    let search = window().location().search().unwrap_or_default();
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|parameter| parameter.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Replace the given URL query parameter without adding a history entry.
+/// An empty value removes the parameter.
+fn set_query_value(name: &str, value: &str) {
+    let location = window().location();
+    let search = location.search().unwrap_or_default();
+    let mut parameters: Vec<_> = search
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|parameter| {
+            let key = parameter.split_once('=').map_or(*parameter, |(key, _)| key);
+            !parameter.is_empty() && key != name
+        })
+        .map(str::to_string)
+        .collect();
+    if !value.is_empty() {
+        parameters.push(format!("{name}={value}"));
+    }
+
+    let query = if parameters.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", parameters.join("&"))
+    };
+    let url = format!(
+        "{}{query}{}",
+        location.pathname().unwrap_or_default(),
+        location.hash().unwrap_or_default()
+    );
+    let result = window()
+        .history()
+        .and_then(|history| history.replace_state_with_url(&JsValue::NULL, "", Some(&url)));
+    if let Err(error) = result {
+        log::warn!("Could not update the URL: {error:?}");
+    }
+}
+
+/// A store row that can be shown in an [`EntityTable`].
+pub trait Entity: Send + Sync + 'static {
+    type Key: Copy + Eq + Hash + Send + Sync + 'static;
+
+    fn key(&self) -> Self::Key;
+
+    /// Refers to the row with the given key from other components, e.g. to highlight it.
+    fn entity_ref(key: Self::Key) -> EntityRef;
+}
+
+/// Refers to an entity of any kind.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum EntityRef {
+    City(CityId),
+    Person(PersonId),
+    HappinessModifier(HappinessModifierId),
+}
+
+/// The entity under the pointer. Every component showing the entity highlights it.
+#[derive(Debug, Clone, Copy)]
+pub struct HoveredEntity(pub RwSignal<Option<EntityRef>>);
+
+impl HoveredEntity {
+    /// Hover the entity until the pointer leaves or the current component unmounts.
+    pub fn track(
+        self,
+        entity: EntityRef,
+    ) -> (
+        impl Fn(web_sys::PointerEvent) + Copy,
+        impl Fn(web_sys::PointerEvent) + Copy,
+    ) {
+        let hovered = self.0;
+        let clear = move || {
+            if hovered.get_untracked() == Some(entity) {
+                hovered.set(None);
+            }
+        };
+        on_cleanup(clear);
+        (move |_| hovered.set(Some(entity)), move |_| clear())
+    }
+}
+
+/// A column of an [`EntityTable`].
+pub struct EntityColumn<T: 'static> {
+    header: &'static str,
+    cell: Arc<dyn Fn(Field<T>) -> AnyView + Send + Sync>,
+    sort_value: Option<Arc<dyn Fn(Field<T>) -> f64 + Send + Sync>>,
+}
+
+impl<T: 'static> EntityColumn<T> {
+    /// Create a column rendering each cell with the given closure.
+    /// The closure may return reactive views.
+    pub fn new<V: IntoView + 'static>(
+        header: &'static str,
+        cell: impl Fn(Field<T>) -> V + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            header,
+            cell: Arc::new(move |row| cell(row).into_any()),
+            sort_value: None,
+        }
+    }
+
+    /// Make the table sortable by this column, comparing the values returned by the given closure.
+    pub fn sortable(
+        mut self,
+        sort_value: impl Fn(Field<T>) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        self.sort_value = Some(Arc::new(sort_value));
+        self
+    }
+}
+
+impl<T: 'static> Clone for EntityColumn<T> {
+    fn clone(&self) -> Self {
+        Self {
+            header: self.header,
+            cell: self.cell.clone(),
+            sort_value: self.sort_value.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+#[component]
+pub fn EntityTable<T>(
+    #[prop(into)] rows: Signal<Vec<Field<T>>>,
+    columns: Vec<EntityColumn<T>>,
+    /// The key of the row to mark as selected.
+    #[prop(optional, into)]
+    selected: Option<Signal<Option<T::Key>>>,
+    /// Called with the key of a row when it is clicked.
+    #[prop(optional, into)]
+    on_row_click: Option<Callback<T::Key>>,
+    /// Called with the key of a row and the client position of a right click on it,
+    /// instead of the browser's menu. The click does not count as a click on the row.
+    #[prop(optional, into)]
+    on_row_context_menu: Option<Callback<(T::Key, ClientPosition)>>,
+    /// Rows for which this returns true are kept above the others, whatever the sort.
+    #[prop(optional, into)]
+    pinned: Option<Callback<Field<T>, bool>>,
+    /// The headers of the columns that are not rendered.
+    #[prop(optional, into)]
+    hidden_columns: Option<Signal<BTreeSet<&'static str>>>,
+    /// The classes of the rows, generic entity rows by default.
+    #[prop(default = styles::ENTITY_ROW)]
+    row_classes: styles::RowClasses,
+) -> impl IntoView
+where
+    T: Entity,
+{
+    let columns = Arc::new(columns);
+    let sort = RwSignal::new(None::<(usize, SortDirection)>);
+    let is_visible = {
+        let columns = columns.clone();
+        move |column: usize| {
+            hidden_columns.is_none_or(|hidden_columns| {
+                !hidden_columns.read().contains(columns[column].header)
+            })
+        }
+    };
+    // Sorting by a hidden column falls back to the default order.
+    let visible_sort = {
+        let is_visible = is_visible.clone();
+        move || sort.get().filter(|(column, _)| is_visible(*column))
+    };
+
+    let sorted_rows = entity_rows_in_order(rows, columns.clone(), visible_sort, pinned);
+
+    let headers = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let header = column.header;
+            let is_visible = is_visible.clone();
+            let sortable = column.sort_value.is_some();
+            let toggle_sort = move |_| {
+                if sortable {
+                    sort.update(|sort| {
+                        *sort = match *sort {
+                            Some((column, SortDirection::Ascending)) if column == index => {
+                                Some((index, SortDirection::Descending))
+                            }
+                            Some((column, SortDirection::Descending)) if column == index => None,
+                            _ => Some((index, SortDirection::Ascending)),
+                        }
+                    })
+                }
+            };
+            let indicator = move || match sort.get() {
+                Some((column, SortDirection::Ascending)) if column == index => " ▲",
+                Some((column, SortDirection::Descending)) if column == index => " ▼",
+                _ => "",
+            };
+
+            move || {
+                is_visible(index).then(|| {
+                    view! {
+                        <th on:click=toggle_sort>{header} {indicator}</th>
+                    }
+                })
+            }
+        })
+        .collect_view();
+
+    let hovered = expect_context::<HoveredEntity>();
+    // Only the rows whose hover state changed are notified, not every row.
+    let is_hovered = Selector::new(move || hovered.0.get());
+
+    view! {
+        <table>
+            <thead>
+                <tr>{headers}</tr>
+            </thead>
+            <tbody>
+                <For
+                    each=sorted_rows
+                    key=|(key, _)| *key
+                    children=move |(key, row)| {
+                        let is_selected = move || {
+                            selected.is_some_and(|selected| selected.get() == Some(key))
+                        };
+                        let entity = T::entity_ref(key);
+                        let (hover, unhover) = hovered.track(entity);
+                        let is_hovered = is_hovered.clone();
+                        // Only the cells are re-rendered when a column is shown or hidden, not the row.
+                        let cells = columns
+                            .iter()
+                            .enumerate()
+                            .map(|(index, column)| {
+                                let cell = column.cell.clone();
+                                let is_visible = is_visible.clone();
+                                move || is_visible(index).then(|| view! { <td>{cell(row)}</td> })
+                            })
+                            .collect_view();
+
+                        let is_pinned = move || pinned.is_some_and(|pinned| pinned.run(row));
+
+                        view! {
+                            <tr
+                                class=row_classes.row
+                                {..styles::toggle(row_classes.selected, is_selected)}
+                                {..styles::toggle(row_classes.hovered, move || is_hovered.selected(Some(entity)))}
+                                {..styles::toggle(row_classes.pinned, is_pinned)}
+                                on:pointerenter=hover
+                                on:pointerleave=unhover
+                                on:click=move |_| {
+                                    if let Some(on_row_click) = on_row_click {
+                                        on_row_click.run(key)
+                                    }
+                                }
+                                on:contextmenu=move |event: web_sys::MouseEvent| {
+                                    if let Some(on_row_context_menu) = on_row_context_menu {
+                                        event.prevent_default();
+                                        on_row_context_menu
+                                            .run((key, (event.client_x(), event.client_y())));
+                                    }
+                                }
+                            >
+                                {cells}
+                            </tr>
+                        }
+                    }
+                />
+            </tbody>
+        </table>
+    }
+}
+
+/// The rows of an [`EntityTable`] with their keys, in the order they are shown.
+///
+/// Only the keys are read untracked, so the order is recomputed when the rows, the sort or
+/// a value sorted by change, not when anything else in a row does.
+fn entity_rows_in_order<T: Entity>(
+    rows: Signal<Vec<Field<T>>>,
+    columns: Arc<Vec<EntityColumn<T>>>,
+    sort: impl Fn() -> Option<(usize, SortDirection)>,
+    pinned: Option<Callback<Field<T>, bool>>,
+) -> impl Fn() -> Vec<(T::Key, Field<T>)> {
+    move || {
+        let _scope = ReadScope::enter();
+        let mut rows: Vec<_> = rows
+            .get()
+            .into_iter()
+            .map(|row| (row.read_untracked().key(), row))
+            .collect();
+        if let Some((column, direction)) = sort() {
+            if let Some(sort_value) = &columns[column].sort_value {
+                let mut keyed: Vec<_> = rows
+                    .into_iter()
+                    .map(|row| (sort_value(row.1), row))
+                    .collect();
+                keyed.sort_by(|(a, _), (b, _)| {
+                    let ordering = a.partial_cmp(b).unwrap_or(CmpOrdering::Equal);
+                    match direction {
+                        SortDirection::Ascending => ordering,
+                        SortDirection::Descending => ordering.reverse(),
+                    }
+                });
+                rows = keyed.into_iter().map(|(_, row)| row).collect();
+            }
+        }
+        if let Some(pinned) = pinned {
+            // Stable, so pinned rows keep their relative order.
+            rows.sort_by_key(|(_, row)| !pinned.run(*row));
+        }
+        rows
+    }
+}
+
+/// Preferences that only affect the presentation, not the simulation.
+#[derive(Debug, Default, Store)]
+pub struct UiPreferences {
+    theme: Theme,
+    /// The headers of the population table columns the user has hidden.
+    hidden_population_columns: BTreeSet<&'static str>,
+    /// Mirrored in the `kinds` and `categories` URL query parameters.
+    modifier_filter: ModifierFilter,
+    /// Only people whose happiness changed by more than this in the last tick are listed.
+    /// Applies together with the `modifier_filter`.
+    min_population_change: Option<f64>,
+    /// In spectator mode, whether the simulation stays paused while the tab is in the background
+    /// instead of catching up on the missed weeks when it returns.
+    pause_in_background: bool,
+}
+
+/// Narrows the population table to people carrying at least one modifier
+/// of a selected kind or category. An empty filter matches everyone.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct ModifierFilter {
+    pub specs: BTreeSet<ModifierSpecId>,
+    pub categories: BTreeSet<ModifierCategory>,
+}
+
+impl ModifierFilter {
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty() && self.categories.is_empty()
+    }
+
+    pub fn matches(&self, person: &Person) -> bool {
+        self.is_empty()
+            || person
+                .happiness
+                .happiness_modifiers
+                .iter()
+                .any(|modifier| self.matches_modifier(modifier))
+    }
+
+    /// The ids of the `people` matching the filter, or `None` if everyone matches.
+    /// The people are read in place, not cloned.
+    pub fn matching_people(&self, people: &[Person]) -> Option<HashSet<PersonId>> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(
+            people
+                .iter()
+                .filter(|person| self.matches(person))
+                .map(|person| person.id)
+                .collect(),
+        )
+    }
+
+    fn matches_modifier(&self, modifier: &HappinessModifier) -> bool {
+        self.specs.contains(&modifier.spec)
+            || modifier
+                .spec
+                .spec()
+                .is_some_and(|spec| self.categories.contains(&spec.category))
+    }
+
+    /// Read the filter from the `kinds` and `categories` URL query parameters.
+    /// Unknown values are ignored.
+    fn from_query() -> Self {
+        let kinds = query_value("kinds").unwrap_or_default();
+        let categories = query_value("categories").unwrap_or_default();
+        Self {
+            specs: kinds
+                .split(',')
+                .filter_map(|id| id.parse().ok())
+                .map(ModifierSpecId)
+                .filter(|id| id.spec().is_some())
+                .collect(),
+            categories: categories
+                .split(',')
+                .filter_map(|name| {
+                    ModifierCategory::ALL
+                        .iter()
+                        .copied()
+                        .find(|category| category.display_name() == name)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Keep the URL query parameters in sync with the modifier filter.
+fn sync_modifier_filter_query(preferences: Store<UiPreferences>) {
+    Effect::new(move || {
+        let filter = preferences.modifier_filter().read();
+        let kinds: Vec<_> = filter.specs.iter().map(|id| id.0.to_string()).collect();
+        let categories: Vec<_> = filter
+            .categories
+            .iter()
+            .map(|category| category.display_name())
+            .collect();
+        set_query_value("kinds", &kinds.join(","));
+        set_query_value("categories", &categories.join(","));
+    });
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Theme {
+    Light,
+    Dark,
+    /// Follow the `prefers-color-scheme` media query.
+    #[default]
+    System,
+}
+
+impl Theme {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Light => Self::Dark,
+            Self::Dark => Self::System,
+            Self::System => Self::Light,
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+            Self::System => "System",
+        }
+    }
+}
+
+/// Keep the `data-theme` attribute of the body in sync with the theme preference.
+fn sync_theme_attribute(preferences: Store<UiPreferences>) {
+    let prefers_dark = RwSignal::new(false);
+
+    if let Ok(Some(media_query)) = window().match_media("(prefers-color-scheme: dark)") {
+        prefers_dark.set(media_query.matches());
+        let listener =
+            Closure::<dyn Fn(MediaQueryListEvent)>::new(move |event: MediaQueryListEvent| {
+                prefers_dark.set(event.matches())
+            });
+        media_query
+            .add_event_listener_with_callback("change", listener.as_ref().unchecked_ref())
+            .unwrap();
+
+        let listener = SendWrapper::new((media_query, listener));
+        on_cleanup(move || {
+            let (media_query, listener) = listener.take();
+            media_query
+                .remove_event_listener_with_callback("change", listener.as_ref().unchecked_ref())
+                .unwrap();
+        });
+    }
+
+    Effect::new(move || {
+        let dark = match preferences.theme().get() {
+            Theme::Light => false,
+            Theme::Dark => true,
+            Theme::System => prefers_dark.get(),
+        };
+        if let Some(body) = document().body() {
+            body.set_attribute("data-theme", if dark { "dark" } else { "light" })
+                .unwrap();
+        }
+    });
+}
+
+/// The most weeks the spectator auto-advance catches up on when the tab returns to the foreground.
+pub const MAX_CATCH_UP_WEEKS: u64 = 52;
+
+/// Browsers throttle intervals in background tabs, so the spectator auto-advance does not tick
+/// while the tab is hidden. When it returns, the missed weeks are fast-forwarded unless the user
+/// chose to pause in the background.
+fn sync_background_auto_advance(
+    state: Store<GlobalState>,
+    preferences: Store<UiPreferences>,
+    announcement: LiveAnnouncement,
+) {
+    let now = || {
+        window()
+            .performance()
+            .map_or(0.0, |performance| performance.now())
+    };
+    let hidden_since = StoredValue::new(document().hidden().then(now));
+    let listener = Closure::<dyn Fn()>::new(move || {
+        if document().hidden() {
+            hidden_since.set_value(Some(now()));
+            return;
+        }
+        let Some(since) = hidden_since.write_value().take() else {
+            return;
+        };
+        if preferences.pause_in_background().get_untracked() {
+            return;
+        }
+        // One week per second, like the auto-advance interval.
+        let missed_weeks = (((now() - since) / 1000.0) as u64).min(MAX_CATCH_UP_WEEKS);
+        // The outcome of the last week is announced once the fast-forward stops.
+        if missed_weeks > 0 && GlobalState::start_fast_forward(state, missed_weeks) {
+            announcement
+                .0
+                .set(format!("Catching up {missed_weeks} missed weeks"));
+        }
+    });
+    document()
+        .add_event_listener_with_callback("visibilitychange", listener.as_ref().unchecked_ref())
+        .unwrap();
+
+    let listener = SendWrapper::new(listener);
+    on_cleanup(move || {
+        let listener = listener.take();
+        document()
+            .remove_event_listener_with_callback(
+                "visibilitychange",
+                listener.as_ref().unchecked_ref(),
+            )
+            .unwrap();
+    });
+}
+
+/// Chooses whether the spectator auto-advance catches up after the tab was in the background.
+#[component]
+fn BackgroundAdvanceToggle() -> impl IntoView {
+    let pause_in_background = expect_context::<Store<UiPreferences>>().pause_in_background();
+
+    view! {
+        <label>
+            <input
+                type="checkbox"
+                prop:checked=move || pause_in_background.get()
+                on:change=move |event| pause_in_background.set(event_target_checked(&event))
+            />
+            " Pause while in the background"
+        </label>
+    }
+}
+
+#[component]
+fn ThemeToggle() -> impl IntoView {
+    let preferences = expect_context::<Store<UiPreferences>>();
+    let theme = preferences.theme();
+    sync_theme_attribute(preferences);
+
+    view! {
+        <button on:click=move |_| theme.update(|theme| *theme = theme.next())>
+            "Theme: " {move || theme.get().display_name()}
+        </button>
+    }
+}
+
+#[component]
+fn InvariantViolationBanner() -> impl IntoView {
+    let state = use_global_state();
+    let violations = state.invariant_violations();
+
+    view! {
+        <Show when=move || !violations.read().is_empty()>
+            <div style="background-color: red; color: white; padding: 0.5em">
+                <strong>Invariant violations</strong>
+                <ul>
+                    {move || {
+                        violations
+                            .read()
+                            .iter()
+                            .map(|violation| view! { <li>{violation.to_string()}</li> })
+                            .collect_view()
+                    }}
+                </ul>
+            </div>
+        </Show>
+    }
+}
+
+#[component]
+fn GoalsPanel() -> impl IntoView {
+    let state = use_global_state();
+    let goals = move || {
+        let week = state.week().get();
+        let (_, average_happiness) = GlobalState::overall_happiness(state);
+        state
+            .goals()
+            .get()
+            .into_iter()
+            .map(|goal| {
+                let progress = goal.kind.progress(week, average_happiness);
+                view! {
+                    <li class=styles::GOAL data-status=goal.status.key()>
+                        {goal.kind.to_string()} " "
+                        <progress max="1" value=progress.to_string() />
+                        {format!(" {}", goal.status.display_name())}
+                    </li>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <section class=styles::GOALS>
+            <h2>Goals</h2>
+            <ul>{goals}</ul>
+        </section>
+    }
+}
+
+/// Shown when a goal failed, offering to restart or keep playing in sandbox mode.
+#[component]
+fn GameOverDialog() -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let generation = expect_context::<GameGeneration>();
+    let restart = move |_| generation.start_new_game();
+
+    view! {
+        <ul>
+            {move || {
+                state
+                    .goals()
+                    .read()
+                    .iter()
+                    .filter(|goal| goal.status == GoalStatus::Failed)
+                    .map(|goal| view! { <li>{format!("Failed: {}", goal.kind)}</li> })
+                    .collect_view()
+            }}
+        </ul>
+        <Show when=move || !read_only.0>
+            <button on:click=restart>Restart</button>
+            <button on:click=move |_| GlobalState::continue_in_sandbox(state)>
+                Continue in sandbox
+            </button>
+        </Show>
+    }
+}
+
+#[derive(Debug, Default, Clone, Store, Serialize, Deserialize)]
+pub struct Population {
+    #[store(skip)]
+    /// Ordered so that iterating the index is deterministic across native and WASM builds.
+    /// Not saved, [`Population::rebuild_index`] restores it on load.
+    #[serde(skip)]
+    people_by_id: BTreeMap<PersonId, usize>,
+    #[store(key: PersonId = |row| row.key())]
+    people: Vec<Person>,
+    /// Removed people with the week of their removal. They are not ticked or listed.
+    trash: Vec<(Person, u64)>,
+}
+
+impl Population {
+    pub fn new(initial_modifiers: &[InitialModifier], ids: &mut IdAllocator) -> Self {
+        let mut people = Vec::new();
+        for _ in 0..5 {
+            people.push(Person::create(0, initial_modifiers, ids));
+        }
+
+        Self {
+            people_by_id: people
+                .iter()
+                .enumerate()
+                .map(|(index, person)| (person.key(), index))
+                .collect(),
+            people,
+            trash: Vec::new(),
+        }
+    }
+
+    /// The returned field is keyed by the id, so it keeps pointing at the person when others
+    /// are added or removed. Once the person leaves the population, it reads as `None`.
+    pub fn person(this: Field<Self>, person_id: PersonId) -> Result<Field<Person>, SimError> {
+        // Look up the index and check the vec under one read guard, so the two cannot disagree.
+        {
+            let population = this.read();
+            let index = *population
+                .people_by_id
+                .get(&person_id)
+                .ok_or(SimError::PersonNotFound(person_id))?;
+            let found = population.people.get(index).map(|person| person.id);
+            if found != Some(person_id) {
+                return Err(SimError::IndexCorrupt(person_id));
+            }
+        }
+        Ok(AtKeyed::new(this.people(), person_id).into())
+    }
+
+    /// Index the people by their position in the vec, replacing the current index.
+    fn rebuild_index(&mut self) {
+        self.people_by_id = self
+            .people
+            .iter()
+            .enumerate()
+            .map(|(index, person)| (person.id, index))
+            .collect();
+    }
+
+    pub fn contains(&self, person_id: PersonId) -> bool {
+        self.people_by_id.contains_key(&person_id)
+    }
+
+    /// Every person with their id, in vec order, resolving the ids in a single untracked read.
+    ///
+    /// The people are taken as of the call, so people added while iterating are not yielded.
+    /// The fields are keyed like the ones of [`Population::person`], so removing people while
+    /// iterating does not disturb the others.
+    pub fn iter_people(this: Field<Self>) -> impl Iterator<Item = (PersonId, Field<Person>)> {
+        let ids: Vec<_> = this
+            .people()
+            .read_untracked()
+            .iter()
+            .map(|person| person.id)
+            .collect();
+        ids.into_iter()
+            .map(move |person_id| (person_id, AtKeyed::new(this.people(), person_id).into()))
+    }
+
+    /// Add a modifier made from `template` to every person for whom `predicate` holds,
+    /// in one write. Returns the number of people affected.
+    ///
+    /// The predicate sees every person as they were before the call, none partially updated.
+    /// The modifier ids are handed out in person id order, not vec order.
+    pub fn apply_modifier_to_matching(
+        this: Field<Self>,
+        predicate: impl Fn(&Person) -> bool,
+        template: ModifierTemplate,
+        week: u64,
+        ids: &mut IdAllocator,
+    ) -> usize {
+        let mut matching: Vec<_> = this
+            .people()
+            .read_untracked()
+            .iter()
+            .filter(|person| predicate(person))
+            .map(|person| person.id)
+            .collect();
+        matching.retain(|&person_id| {
+            !exceeds_mutation_rate(person_id, "Population::apply_modifier_to_matching")
+        });
+        if matching.is_empty() {
+            return 0;
+        }
+        matching.sort_unstable();
+        let mut modifiers: BTreeMap<_, _> = matching
+            .iter()
+            .map(|&person_id| (person_id, template.instantiate(week, ids)))
+            .collect();
+
+        for person in this.people().write().iter_mut() {
+            if let Some(modifier) = modifiers.remove(&person.id) {
+                person.happiness.happiness_modifiers.push(modifier);
+            }
+        }
+        record_mutation(
+            week,
+            || {
+                format!(
+                    "people[{} matching].happiness.happiness_modifiers",
+                    matching.len()
+                )
+            },
+            MutationKind::Insert,
+            "Population::apply_modifier_to_matching",
+        );
+        matching.len()
+    }
+
+    /// Insert a person, keeping the index and the keyed fields consistent.
+    pub fn add_person(this: Field<Self>, person: Person) {
+        {
+            let mut population = this.write();
+            let index = population.people.len();
+            population.people_by_id.insert(person.id, index);
+            population.people.push(person);
+        }
+        // Written through the population, which does not refresh the keys of its people.
+        this.people().update_keys();
+    }
+
+    /// Remove a person, keeping the index and the keyed fields consistent.
+    pub fn remove_person(this: Field<Self>, person_id: PersonId) -> Option<Person> {
+        let person = {
+            let mut population = this.write();
+            let index = population.people_by_id.remove(&person_id)?;
+            let person = population.people.remove(index);
+            for other_index in population.people_by_id.values_mut() {
+                if *other_index > index {
+                    *other_index -= 1;
+                }
+            }
+            person
+        };
+        this.people().update_keys();
+        Some(person)
+    }
+
+    /// Remove every modifier of every person in one write. Returns the number removed.
+    pub fn reset_modifiers(this: Field<Self>, week: u64) -> usize {
+        let mut removed = 0;
+        for person in this.people().write().iter_mut() {
+            removed += person.happiness.happiness_modifiers.len();
+            person.happiness.happiness_modifiers.clear();
+        }
+        record_mutation(
+            week,
+            || "people[*].happiness.happiness_modifiers".to_string(),
+            MutationKind::Remove,
+            "Population::reset_modifiers",
+        );
+        removed
+    }
+
+    /// Move a person to the trash, remembering the week of the removal.
+    pub fn trash_person(this: Field<Self>, person_id: PersonId, week: u64) -> bool {
+        let Some(person) = Self::remove_person(this, person_id) else {
+            return false;
+        };
+        this.trash().write().push((person, week));
+        true
+    }
+
+    /// Move a person from the trash back to the people.
+    pub fn restore_person(this: Field<Self>, person_id: PersonId) -> bool {
+        let Some(index) = this
+            .trash()
+            .read_untracked()
+            .iter()
+            .position(|(person, _)| person.id == person_id)
+        else {
+            return false;
+        };
+        let (person, _) = this.trash().write().remove(index);
+        Self::add_person(this, person);
+        true
+    }
+
+    /// Drop the people removed before the given week, returning their ids.
+    pub fn purge_trash(this: Field<Self>, oldest_kept_week: u64) -> Vec<PersonId> {
+        let expired = |&(_, removed_week): &(Person, u64)| removed_week < oldest_kept_week;
+        if !this.trash().read_untracked().iter().any(expired) {
+            return Vec::new();
+        }
+
+        let mut purged = Vec::new();
+        this.trash().write().retain(|entry| {
+            if expired(entry) {
+                purged.push(entry.0.id);
+                false
+            } else {
+                true
+            }
+        });
+        purged
+    }
+
+    /// The average total happiness of all people, or `None` if the population is empty.
+    pub fn average_happiness(this: Field<Self>) -> Option<f64> {
+        let count = this.people().read().len();
+        if count == 0 {
+            return None;
+        }
+
+        let total: f64 = Self::iter_people(this)
+            .map(|(_, person)| Happiness::total(person.happiness().into()))
+            .sum();
+        Some(total / count as f64)
+    }
+
+    /// `ids` is the allocator of the state, which must be ahead of every id in use.
+    pub fn validate(&self, ids: &IdAllocator) -> Vec<InvariantViolation> {
+        let mut violations = Vec::new();
+
+        for (&person_id, &index) in &self.people_by_id {
+            match self.people.get(index) {
+                None => violations.push(InvariantViolation::IndexOutOfBounds { person_id, index }),
+                Some(person) if person.id != person_id => {
+                    violations.push(InvariantViolation::IndexPointsToWrongPerson {
+                        person_id,
+                        index,
+                        actual_person_id: person.id,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut person_ids = HashSet::new();
+        let mut modifier_ids = HashSet::new();
+        let next_person_id = ids.next_person_id;
+        let next_modifier_id = ids.next_modifier_id;
+        for (index, person) in self.people.iter().enumerate() {
+            if self.people_by_id.get(&person.id) != Some(&index) {
+                violations.push(InvariantViolation::PersonNotIndexed {
+                    person_id: person.id,
+                    index,
+                });
+            }
+            if !person_ids.insert(person.id) {
+                violations.push(InvariantViolation::DuplicatePersonId(person.id));
+            }
+            if person.id.0 >= next_person_id {
+                violations.push(InvariantViolation::PersonIdAllocatorBehind {
+                    next: next_person_id,
+                    existing: person.id,
+                });
+            }
+
+            for modifier in &person.happiness.happiness_modifiers {
+                if !modifier_ids.insert(modifier.id) {
+                    violations.push(InvariantViolation::DuplicateHappinessModifierId(
+                        modifier.id,
+                    ));
+                }
+                if modifier.spec.spec().is_none() {
+                    violations.push(InvariantViolation::UnknownModifierSpec {
+                        modifier_id: modifier.id,
+                        spec_id: modifier.spec,
+                    });
+                }
+                if modifier.id.0 >= next_modifier_id {
+                    violations.push(InvariantViolation::HappinessModifierIdAllocatorBehind {
+                        next: next_modifier_id,
+                        existing: modifier.id,
+                    });
+                }
+            }
+        }
+
+        for (person, _) in &self.trash {
+            if !person_ids.insert(person.id) {
+                violations.push(InvariantViolation::DuplicatePersonId(person.id));
+            }
+        }
+
+        violations
+    }
+
+    /// The people in ascending id order, which unlike the vec order
+    /// does not depend on the order in which they were added.
+    pub fn people_in_id_order(&self) -> impl Iterator<Item = &Person> {
+        self.people_by_id.values().map(|&index| &self.people[index])
+    }
+
+    pub fn finish_week(
+        this: Field<Self>,
+        week: u64,
+        config: &SimConfig,
+        stats: &mut ModifierStats,
+        ids: &mut IdAllocator,
+    ) {
+        // Finish weeks for people, in id order so that the result does not depend on the vec order.
+        let mut people: Vec<_> = Self::iter_people(this).collect();
+        people.sort_unstable_by_key(|&(person_id, _)| person_id);
+        for (_, person) in people {
+            if !person.paused().get_untracked() {
+                Person::finish_week(person, week, config, stats, ids);
+            }
+        }
+    }
+}
+
+#[component]
+pub fn PopulationTable(
+    city_id: CityId,
+    #[prop(into)] selected: Signal<Option<PersonId>>,
+    #[prop(into)] on_select: Callback<PersonId>,
+) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let people = GlobalState::city(state, city_id)?.population().people();
+    let preferences = expect_context::<Store<UiPreferences>>();
+    let filter = preferences.modifier_filter();
+    let matching = Memo::new(move |_| {
+        let _scope = ReadScope::enter();
+        let filter = filter.read();
+        // Checked first, so that an empty filter does not track the people.
+        if filter.is_empty() {
+            return None;
+        }
+        filter.matching_people(&people.read())
+    });
+    let min_change = preferences.min_population_change();
+    let rows = Signal::derive(move || {
+        let _scope = ReadScope::enter();
+        let matching = matching.read();
+        let min_change = min_change.get();
+        people
+            .into_iter()
+            .filter(|person| {
+                matching
+                    .as_ref()
+                    .is_none_or(|matching| matching.contains(&person.id().get()))
+            })
+            .filter(|person| {
+                min_change.is_none_or(|min_change| {
+                    person
+                        .happiness()
+                        .last_week_delta()
+                        .get()
+                        .is_some_and(|delta| delta.abs() > min_change)
+                })
+            })
+            .map(Into::into)
+            .collect()
+    });
+    let columns = vec![
+        EntityColumn::new("Id", |person: Field<Person>| {
+            view! {
+                {person.id().get().to_string()}
+                <Show when=move || person.paused().get()>
+                    <span title="Paused">" ⏸"</span>
+                </Show>
+            }
+        })
+        .sortable(|person| person.id().get().0 as f64),
+        // The cells memoize their values, so a tick that leaves a person's numbers unchanged
+        // does not touch their row. The memos are created when the cell is first rendered.
+        EntityColumn::new("Modifiers", |person: Field<Person>| {
+            let count = Memo::new(move |_| person.happiness().happiness_modifiers().read().len());
+            move || count.get()
+        })
+        .sortable(|person| person.happiness().happiness_modifiers().read().len() as f64),
+        EntityColumn::new("Happiness", |person: Field<Person>| {
+            let total = Memo::new(move |_| Happiness::total(person.happiness().into()));
+            view! {
+                <HappinessBadge happiness=total compact=true />
+                " "
+                {move || format!("{:.0}%", total.get() * 100.0)}
+            }
+        })
+        .sortable(|person| Happiness::total(person.happiness().into())),
+        EntityColumn::new("Change", |person: Field<Person>| {
+            let delta = person.happiness().last_week_delta();
+            view! {
+                <span
+                    class={styles::DELTA}
+                    {..styles::toggle(
+                        styles::DELTA_POSITIVE,
+                        move || delta.get().is_some_and(|delta| delta > 0.0),
+                    )}
+                    {..styles::toggle(
+                        styles::DELTA_NEGATIVE,
+                        move || delta.get().is_some_and(|delta| delta < 0.0),
+                    )}
+                >
+                    {move || {
+                        delta
+                            .get()
+                            .map_or("new".to_string(), |delta| format!("{:+.0}%", delta * 100.0))
+                    }}
+                </span>
+            }
+        })
+        // New people sort below everyone who has a change.
+        .sortable(|person| {
+            person
+                .happiness()
+                .last_week_delta()
+                .get()
+                .unwrap_or(f64::NEG_INFINITY)
+        }),
+    ];
+
+    let hidden_columns = preferences.hidden_population_columns();
+    let headers = columns.iter().map(|column| column.header).collect();
+
+    let read_only = expect_context::<ReadOnly>();
+    let selection = expect_context::<Selection>();
+    let announcement = expect_context::<LiveAnnouncement>().0;
+    // Opening the menu leaves the selection alone, only "View" selects the person.
+    let open_menu = Callback::new(move |(person_id, position): (PersonId, ClientPosition)| {
+        let mut actions = vec![ContextMenuAction::new("View", move || {
+            on_select.run(person_id)
+        })];
+        if !read_only.0 {
+            actions.push(ContextMenuAction::new("Add modifier…", move || {
+                open_dialog(DialogRequest::AddModifier { person_id });
+            }));
+            actions.push(ContextMenuAction::new(
+                "Remove",
+                move || match remove_person(state, person_id, announcement) {
+                    Ok(()) => {
+                        if selection.person.get_untracked() == Some(person_id) {
+                            selection.person.set(None);
+                        }
+                    }
+                    Err(sim_error) => announcement.set(format!("Could not remove: {sim_error}")),
+                },
+            ));
+        }
+        open_context_menu(position, actions);
+    });
+
+    Ok(view! {
+        <ModifierFilterPicker filter />
+        <label>
+            "Changed by more than "
+            <input
+                type="number"
+                min="0"
+                prop:value=move || {
+                    min_change.get().map(|change| (change * 100.0).to_string()).unwrap_or_default()
+                }
+                on:change=move |event| {
+                    let percent = event_target_value(&event).trim().parse::<f64>().ok();
+                    min_change.set(percent.map(|percent| percent / 100.0));
+                }
+            />
+            "%"
+        </label>
+        <ColumnPicker headers hidden_columns />
+        <EntityTable
+            rows
+            columns
+            selected
+            on_row_click=on_select
+            on_row_context_menu=open_menu
+            hidden_columns=Signal::derive(move || hidden_columns.get())
+            row_classes=styles::PERSON_ROW
+        />
+    })
+}
+
+/// A dropdown of checkboxes selecting the modifier kinds and categories to filter by.
+#[component]
+fn ModifierFilterPicker(#[prop(into)] filter: Field<ModifierFilter>) -> impl IntoView {
+    let kinds = MODIFIER_SPECS
+        .iter()
+        .map(|spec| {
+            let id = spec.id;
+            view! {
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || filter.read().specs.contains(&id)
+                        on:change=move |_| {
+                            filter.update(|filter| {
+                                if !filter.specs.remove(&id) {
+                                    filter.specs.insert(id);
+                                }
+                            })
+                        }
+                    />
+                    {spec.name}
+                </label>
+            }
+        })
+        .collect_view();
+    let categories = ModifierCategory::ALL
+        .iter()
+        .map(|&category| {
+            view! {
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || filter.read().categories.contains(&category)
+                        on:change=move |_| {
+                            filter.update(|filter| {
+                                if !filter.categories.remove(&category) {
+                                    filter.categories.insert(category);
+                                }
+                            })
+                        }
+                    />
+                    {category.display_name()}
+                </label>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <details>
+            <summary>
+                {move || {
+                    if filter.read().is_empty() { "Filter" } else { "Filter (active)" }
+                }}
+            </summary>
+            <fieldset>
+                <legend>Kinds</legend>
+                {kinds}
+            </fieldset>
+            <fieldset>
+                <legend>Categories</legend>
+                {categories}
+            </fieldset>
+        </details>
+    }
+}
+
+/// A dropdown of checkboxes toggling the visibility of table columns.
+/// The last visible column cannot be hidden.
+#[component]
+fn ColumnPicker(
+    headers: Vec<&'static str>,
+    #[prop(into)] hidden_columns: Field<BTreeSet<&'static str>>,
+) -> impl IntoView {
+    let column_count = headers.len();
+    let options = headers
+        .into_iter()
+        .map(|header| {
+            let is_hidden = move || hidden_columns.read().contains(header);
+            let is_last_visible =
+                move || !is_hidden() && hidden_columns.read().len() + 1 >= column_count;
+            let toggle = move |_| {
+                hidden_columns.update(|hidden_columns| {
+                    if !hidden_columns.remove(header) && hidden_columns.len() + 1 < column_count {
+                        hidden_columns.insert(header);
+                    }
+                })
+            };
+
+            view! {
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || !is_hidden()
+                        disabled=is_last_visible
+                        on:change=toggle
+                    />
+                    {header}
+                </label>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <details>
+            <summary>Columns</summary>
+            {options}
+        </details>
+    }
+}
+
+#[component]
+pub fn PopulationSummary(city_id: CityId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let population = GlobalState::city(state, city_id)?.population();
+    let count = move || population.people().read().len();
+    let average = Memo::new(move |_| {
+        let _scope = ReadScope::enter();
+        Population::average_happiness(population.into())
+    });
+    let average_happiness = move || {
+        average
+            .get()
+            .map(|happiness| format!("{:.0}%", happiness * 100.0))
+            .unwrap_or_else(|| "—".to_string())
+    };
+    let badge = move || {
+        average.get().map(|happiness| {
+            view! {
+                " "
+                <HappinessBadge happiness />
+            }
+        })
+    };
+
+    Ok(view! {
+        <p class=styles::SUMMARY>
+            "Population: " <span class=styles::SUMMARY_VALUE>{count}</span>
+            ", average happiness: " <span class=styles::SUMMARY_VALUE>{average_happiness}</span>
+            {badge}
+        </p>
+    })
+}
+
+/// How happy someone is, by which thresholds of the [`SimConfig`] their total happiness reaches.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HappinessBand {
+    Miserable,
+    Unhappy,
+    Content,
+    Happy,
+    Thriving,
+}
+
+impl HappinessBand {
+    /// The band of `happiness` given the lowest happiness of each band above `Miserable`,
+    /// in ascending order.
+    ///
+    /// A value exactly on a threshold belongs to the band above it. The thresholds are
+    /// checked from the highest band down, so if they are not ascending, a band whose
+    /// threshold is not below the next one's is never returned.
+    pub fn of(happiness: f64, thresholds: [f64; 4]) -> Self {
+        let [unhappy, content, happy, thriving] = thresholds;
+        if happiness >= thriving {
+            Self::Thriving
+        } else if happiness >= happy {
+            Self::Happy
+        } else if happiness >= content {
+            Self::Content
+        } else if happiness >= unhappy {
+            Self::Unhappy
+        } else {
+            Self::Miserable
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Miserable => "Miserable",
+            Self::Unhappy => "Unhappy",
+            Self::Content => "Content",
+            Self::Happy => "Happy",
+            Self::Thriving => "Thriving",
+        }
+    }
+
+    /// The value of the `data-band` attribute the stylesheet colors by.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Miserable => "miserable",
+            Self::Unhappy => "unhappy",
+            Self::Content => "content",
+            Self::Happy => "happy",
+            Self::Thriving => "thriving",
+        }
+    }
+}
+
+/// A colored dot for the [`HappinessBand`] of `happiness`, followed by the band's name
+/// unless `compact`. Follows edits of the thresholds in the settings.
+#[component]
+pub fn HappinessBadge(
+    #[prop(into)] happiness: Signal<f64>,
+    #[prop(optional)] compact: bool,
+) -> impl IntoView {
+    let config = use_global_state().config();
+    let band = Memo::new(move |_| {
+        let thresholds = [
+            config.unhappy_threshold().get(),
+            config.content_threshold().get(),
+            config.happy_threshold().get(),
+            config.thriving_threshold().get(),
+        ];
+        HappinessBand::of(happiness.get(), thresholds)
+    });
+    let label = move || band.get().label();
+
+    view! {
+        <span class=styles::HAPPINESS_BADGE data-band=move || band.get().key()>
+            <span
+                class=styles::HAPPINESS_BADGE_DOT
+                title=label
+                role="img"
+                aria-label=label
+            ></span>
+            <Show when=move || !compact>
+                " " <span class=styles::HAPPINESS_BADGE_LABEL>{label}</span>
+            </Show>
+        </span>
+    }
+}
+
+/// Hands out the ids of one game, counting from 1 for both kinds. Each [`GlobalState`] has its
+/// own, so two games started with the same seed give out the same ids.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IdAllocator {
+    next_person_id: u64,
+    next_modifier_id: u64,
+}
+
+impl Default for IdAllocator {
+    fn default() -> Self {
+        Self {
+            next_person_id: 1,
+            next_modifier_id: 1,
+        }
+    }
+}
+
+impl IdAllocator {
+    fn person_id(&mut self) -> PersonId {
+        self.next_person_id += 1;
+        PersonId(self.next_person_id - 1)
+    }
+
+    fn modifier_id(&mut self) -> HappinessModifierId {
+        self.next_modifier_id += 1;
+        HappinessModifierId(self.next_modifier_id - 1)
+    }
+}
+
+#[derive(Debug, Clone, Store, Serialize, Deserialize)]
+pub struct Person {
+    id: PersonId,
+    happiness: Happiness,
+    /// Free-text notes, rendered with [`parse_notes`].
+    notes: String,
+    /// Paused people are skipped by the weekly tick and never migrate,
+    /// but still count toward population sizes and averages.
+    paused: bool,
+}
+
+/// Shown and parsed as a plain number, e.g. `42`. Ids start at 1.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u64")]
+pub struct PersonId(u64);
+
+impl fmt::Display for PersonId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<u64> for PersonId {
+    type Error = ParseIdError;
+
+    fn try_from(id: u64) -> Result<Self, ParseIdError> {
+        if id == 0 {
+            return Err(ParseIdError::Zero);
+        }
+        Ok(Self(id))
+    }
+}
+
+impl FromStr for PersonId {
+    type Err = ParseIdError;
+
+    fn from_str(text: &str) -> Result<Self, ParseIdError> {
+        let id: u64 = text.parse().map_err(|_| ParseIdError::NotANumber)?;
+        id.try_into()
+    }
+}
+
+/// Why a [`PersonId`] or [`HappinessModifierId`] could not be parsed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ParseIdError {
+    NotANumber,
+    /// Ids are allocated starting at 1, so 0 never refers to anything.
+    Zero,
+}
+
+impl fmt::Display for ParseIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotANumber => write!(f, "not a number"),
+            Self::Zero => write!(f, "ids start at 1"),
+        }
+    }
+}
+
+impl Person {
+    pub fn create(week: u64, initial_modifiers: &[InitialModifier], ids: &mut IdAllocator) -> Self {
+        Self {
+            id: ids.person_id(),
+            happiness: Happiness::new_initial(week, initial_modifiers, ids),
+            notes: String::new(),
+            paused: false,
+        }
+    }
+
+    pub fn finish_week(
+        this: Field<Self>,
+        week: u64,
+        config: &SimConfig,
+        stats: &mut ModifierStats,
+        ids: &mut IdAllocator,
+    ) {
+        Happiness::finish_week(this, week, config, stats, ids);
+    }
+}
+
+impl Entity for Person {
+    type Key = PersonId;
+
+    fn key(&self) -> PersonId {
+        self.id
+    }
+
+    fn entity_ref(key: PersonId) -> EntityRef {
+        EntityRef::Person(key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum PersonTab {
+    Modifiers,
+    Notes,
+    History,
+}
+
+/// The fallback of an [`ErrorBoundary`] around components that fail with a [`SimError`].
+fn show_errors(errors: ArcRwSignal<Errors>) -> impl IntoView {
+    move || {
+        errors
+            .get()
+            .into_iter()
+            .map(|(_, error)| view! { <p class=styles::ERROR>{error.to_string()}</p> })
+            .collect_view()
+    }
+}
+
+#[component]
+pub fn PersonView(city_id: CityId, person_id: PersonId) -> impl IntoView {
+    let tab = RwSignal::new(PersonTab::Modifiers);
+    // The components below fail with a SimError if the person is gone, e.g. removed by a tick.
+    view! {
+        <ErrorBoundary fallback=show_errors>
+            <PersonHeader city_id person_id />
+            <PersonMigration city_id person_id />
+            <PauseToggle city_id person_id />
+            <RemovePersonButton person_id />
+            <WhatIfPanel city_id person_id />
+            <HappinessExplanation city_id person_id />
+            <nav>
+                <button
+                    disabled=move || tab.get() == PersonTab::Modifiers
+                    on:click=move |_| tab.set(PersonTab::Modifiers)
+                >
+                    Modifiers
+                </button>
+                <button
+                    disabled=move || tab.get() == PersonTab::Notes
+                    on:click=move |_| tab.set(PersonTab::Notes)
+                >
+                    Notes
+                </button>
+                <button
+                    disabled=move || tab.get() == PersonTab::History
+                    on:click=move |_| tab.set(PersonTab::History)
+                >
+                    History
+                </button>
+            </nav>
+            {move || match tab.get() {
+                PersonTab::Modifiers => {
+                    view! { <HappinessModifierTable city_id person_id=person_id /> }.into_any()
+                }
+                PersonTab::Notes => view! { <PersonNotes city_id person_id /> }.into_any(),
+                PersonTab::History => {
+                    view! {
+                        <HappinessForecast city_id person_id />
+                        <ModifierTimeline city_id person_id />
+                    }
+                        .into_any()
+                }
+            }}
+        </ErrorBoundary>
+    }
+}
+
+#[component]
+fn PersonHeader(city_id: CityId, person_id: PersonId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let population = GlobalState::city(state, city_id)?.population();
+    let person = Population::person(population.into(), person_id)?;
+    let total = Memo::new(move |_| Happiness::total(person.happiness().into()));
+
+    Ok(view! {
+        <h3>
+            "Person " {person_id.to_string()} ": "
+            {move || format!("{:.0}%", total.get() * 100.0)} " " <HappinessBadge happiness=total />
+        </h3>
+    })
+}
+
+/// An expandable list of the steps of [`Happiness::explain`].
+#[component]
+fn HappinessExplanation(city_id: CityId, person_id: PersonId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let population = GlobalState::city(state, city_id)?.population();
+    let person = Population::person(population.into(), person_id)?;
+    let percent = |happiness: f64| format!("{:+.0}%", happiness * 100.0);
+    let steps = move || {
+        let explanation = Happiness::explain(person.happiness().into());
+        let modifiers = explanation.modifiers.into_iter().map(|modifier| {
+            let name = modifier.spec.spec().map_or("Unknown", |spec| spec.name);
+            let source = if modifier.is_override {
+                "overridden magnitude"
+            } else {
+                "base magnitude"
+            };
+            view! {
+                <li>
+                    <Icon icon=modifier.spec.icon() />
+                    {format!(" {name}: {} ({source})", percent(modifier.happiness))}
+                </li>
+            }
+        });
+        let categories = explanation.by_category.into_iter().map(|(category, happiness)| {
+            view! { <li>{format!("{} sum: {}", category.display_name(), percent(happiness))}</li> }
+        });
+        view! {
+            {modifiers.collect_view()}
+            {categories.collect_view()}
+            <li>{format!("Total, the sum of all modifiers: {}", percent(explanation.total))}</li>
+        }
+    };
+
+    Ok(view! {
+        <details>
+            <summary>Explain</summary>
+            <ol>{steps}</ol>
+        </details>
+    })
+}
+
+/// The number of weeks [`HappinessForecast`] projects.
+pub const FORECAST_WEEKS: u32 = 12;
+
+/// A dashed line of a person's projected total happiness, starting from the current total.
+#[component]
+fn HappinessForecast(city_id: CityId, person_id: PersonId) -> Result<impl IntoView, SimError> {
+    const WIDTH: f64 = 240.0;
+    const HEIGHT: f64 = 60.0;
+
+    let state = use_global_state();
+    let population = GlobalState::city(state, city_id)?.population();
+    let person = Population::person(population.into(), person_id)?;
+    let config = state.config();
+    // Only the modifiers, the pause and the decay settings change the projection.
+    let totals = Memo::new(move |_| {
+        let happiness = person.happiness();
+        happiness.happiness_modifiers().track();
+        let happiness = happiness.read_untracked();
+        let current = happiness.current_total();
+        let mut totals = vec![current];
+        if person.paused().get() {
+            totals.extend(std::iter::repeat_n(current, FORECAST_WEEKS as usize));
+        } else {
+            let config = SimConfig {
+                modifier_decay_rate: config.modifier_decay_rate().get(),
+                modifier_decay_threshold: config.modifier_decay_threshold().get(),
+                ..config.get_untracked()
+            };
+            totals.extend(happiness.forecast(FORECAST_WEEKS, &config));
+        }
+        totals
+    });
+    let points = move || {
+        let totals = totals.read();
+        let low = totals.iter().copied().fold(0.0, f64::min);
+        let high = totals.iter().copied().fold(0.0, f64::max);
+        let range = (high - low).max(f64::EPSILON);
+        totals
+            .iter()
+            .enumerate()
+            .map(|(week, total)| {
+                let x = week as f64 / FORECAST_WEEKS as f64 * WIDTH;
+                let y = (high - total) / range * HEIGHT;
+                format!("{x:.1},{y:.1}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let summary = move || {
+        totals
+            .read()
+            .last()
+            .map(|total| format!("In {FORECAST_WEEKS} weeks: {:.0}%", total * 100.0))
+    };
+
+    Ok(view! {
+        <figure>
+            <svg
+                width=WIDTH
+                height=HEIGHT
+                viewBox=format!("0 0 {WIDTH} {HEIGHT}")
+                role="img"
+                aria-label=summary
+            >
+                <polyline
+                    points=points
+                    fill="none"
+                    stroke="currentColor"
+                    stroke-dasharray="4 3"
+                />
+            </svg>
+            <figcaption>{summary}</figcaption>
+        </figure>
+    })
+}
+
+/// A scrubber over the weeks of a person's [`ModifierHistory`], showing their modifiers
+/// as they were in the chosen week. Rendered from the snapshots, so it is read-only.
+#[component]
+fn ModifierTimeline(city_id: CityId, person_id: PersonId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let population = GlobalState::city(state, city_id)?.population();
+    let history = Population::person(population.into(), person_id)?
+        .happiness()
+        .history();
+    // Follows the current week until the user scrubs. Hovering a week previews it.
+    let scrubbed_week = RwSignal::new(None::<u64>);
+    let hovered_week = RwSignal::new(None::<u64>);
+    let first_week = move || {
+        state
+            .week()
+            .get()
+            .saturating_sub(MODIFIER_HISTORY_WEEKS as u64 - 1)
+    };
+    let shown_week = move || {
+        hovered_week
+            .get()
+            .or(scrubbed_week.get())
+            .unwrap_or_else(|| state.week().get())
+    };
+    let scrub = move |event| {
+        if let Ok(week) = event_target_value(&event).parse() {
+            scrubbed_week.set(Some(week));
+        }
+    };
+    let ticks = move || {
+        (first_week()..=state.week().get())
+            .map(|week| {
+                let exists = move || history.read().at(week).is_some();
+                view! {
+                    <span
+                        title=format!("Week {week}")
+                        style:opacity=move || if exists() { "1" } else { "0.3" }
+                        on:pointerenter=move |_| hovered_week.set(Some(week))
+                    >
+                        "|"
+                    </span>
+                }
+            })
+            .collect_view()
+    };
+    let snapshot_rows = move || {
+        let history = history.read();
+        let Some(snapshot) = history.at(shown_week()) else {
+            return view! {
+                <tr>
+                    <td colspan="2">"Not around yet"</td>
+                </tr>
+            }
+            .into_any();
+        };
+        snapshot
+            .iter()
+            .map(|(spec_id, magnitude)| {
+                view! {
+                    <tr>
+                        <td>
+                            <Icon icon=spec_id.icon() />
+                            {format!(" {}", spec_id.spec().map_or("Unknown", |spec| spec.name))}
+                        </td>
+                        <td>{format!("{:.0}%", magnitude * 100.0)}</td>
+                    </tr>
+                }
+            })
+            .collect_view()
+            .into_any()
+    };
+
+    Ok(view! {
+        <section>
+            <p>
+                <input
+                    type="range"
+                    min=move || first_week().to_string()
+                    max=move || state.week().get().to_string()
+                    prop:value=move || shown_week().to_string()
+                    on:input=scrub
+                />
+                {move || format!(" Week {}", shown_week())}
+            </p>
+            <p on:pointerleave=move |_| hovered_week.set(None)>{ticks}</p>
+            <table>
+                <thead>
+                    <tr>
+                        <th>Kind</th>
+                        <th>Magnitude</th>
+                    </tr>
+                </thead>
+                <tbody>{snapshot_rows}</tbody>
+            </table>
+        </section>
+    })
+}
+
+/// Previews the happiness of a person with an extra modifier, and adds it on request.
+#[component]
+fn WhatIfPanel(city_id: CityId, person_id: PersonId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let population = GlobalState::city(state, city_id)?.population();
+    let modifiers = Population::person(population.into(), person_id)?
+        .happiness()
+        .happiness_modifiers();
+    let spec_id = RwSignal::new(ModifierSpecId::DEFAULT);
+    let spec = move || spec_id.get().spec();
+    let magnitude = RwSignal::new(spec().map_or(0.0, |spec| spec.base_magnitude));
+
+    let current = Memo::new(move |_| {
+        Happiness::breakdown(
+            modifiers
+                .read()
+                .iter()
+                .map(|modifier| (modifier.spec, modifier.current_magnitude())),
+        )
+    });
+    // Computed from a snapshot of the modifiers, nothing is written to the store.
+    let preview = Memo::new(move |_| {
+        let candidate = (spec_id.get(), magnitude.get());
+        Happiness::breakdown(
+            modifiers
+                .read()
+                .iter()
+                .map(|modifier| (modifier.spec, modifier.current_magnitude()))
+                .chain([candidate]),
+        )
+    });
+
+    let options = MODIFIER_SPECS
+        .iter()
+        .map(|modifier_spec| {
+            view! {
+                <option
+                    value=modifier_spec.id.0.to_string()
+                    selected=move || spec_id.get() == modifier_spec.id
+                >
+                    {modifier_spec.name}
+                </option>
+            }
+        })
+        .collect_view();
+    let select_spec = move |event| {
+        let Ok(id) = event_target_value(&event).parse() else {
+            return;
+        };
+        let new_spec = ModifierSpecId(id);
+        spec_id.set(new_spec);
+        magnitude.set(new_spec.spec().map_or(0.0, |spec| spec.base_magnitude));
+    };
+    let categories = move || {
+        let current = current.get();
+        preview
+            .get()
+            .by_category
+            .into_iter()
+            .map(|(category, happiness)| {
+                let before = current.by_category.get(&category).copied().unwrap_or(0.0);
+                view! {
+                    <li>
+                        {format!(
+                            "{}: {:.0}% → {:.0}%",
+                            category.display_name(),
+                            before * 100.0,
+                            happiness * 100.0,
+                        )}
+                    </li>
+                }
+            })
+            .collect_view()
+    };
+    let apply = move |_| {
+        let spec_id = spec_id.get_untracked();
+        if let Some(modifier_id) = GlobalState::add_pinned_modifier(
+            state,
+            person_id,
+            spec_id,
+            Some(magnitude.get_untracked()),
+        ) {
+            show_added_modifier_toast(state, person_id, spec_id, modifier_id);
+        }
+    };
+
+    Ok(view! {
+        <details>
+            <summary>What if</summary>
+            {move || view! { <Icon icon=spec_id.get().icon() /> }}
+            " "
+            <select on:change=select_spec>{options}</select>
+            <input
+                type="range"
+                step="0.01"
+                min=move || spec().map_or(0.0, |spec| spec.min_magnitude).to_string()
+                max=move || spec().map_or(0.0, |spec| spec.max_magnitude).to_string()
+                prop:value=move || magnitude.get().to_string()
+                on:input=move |event| {
+                    if let Ok(value) = event_target_value(&event).parse() {
+                        magnitude.set(value);
+                    }
+                }
+            />
+            {move || format!(" {:.0}%", magnitude.get() * 100.0)}
+            <p>
+                {move || {
+                    format!(
+                        "Total happiness: {:.0}% → {:.0}%",
+                        current.get().total * 100.0,
+                        preview.get().total * 100.0,
+                    )
+                }}
+            </p>
+            <ul>{categories}</ul>
+            <Show when=move || !read_only.0>
+                <button on:click=apply>Apply</button>
+            </Show>
+        </details>
+    })
+}
+
+/// Tells that a modifier was added by hand, with its icon, and offers to remove it again.
+fn show_added_modifier_toast(
+    state: Store<GlobalState>,
+    person_id: PersonId,
+    spec_id: ModifierSpecId,
+    modifier_id: HappinessModifierId,
+) {
+    let name = spec_id.spec().map_or("Unknown", |spec| spec.name);
+    show_icon_undo_toast(
+        spec_id.icon(),
+        format!("{name} added to person {person_id}."),
+        move || {
+            // Fails if the person or the modifier is gone in the meantime.
+            GlobalState::remove_modifier(state, person_id, modifier_id)
+                .map_err(|error| format!("Could not remove the {name} modifier: {error}"))
+        },
+    );
+}
+
+/// Picks the kind and magnitude of a modifier and adds it to a person, see
+/// [`DialogRequest::AddModifier`].
+#[component]
+fn AddModifierDialog(person_id: PersonId, handle: DialogHandle) -> impl IntoView {
+    let state = use_global_state();
+    let spec_id = RwSignal::new(ModifierSpecId::DEFAULT);
+    let base_percent =
+        |spec_id: ModifierSpecId| spec_id.spec().map_or(0.0, |spec| spec.base_magnitude) * 100.0;
+    // The magnitude being entered, in percent.
+    let draft = RwSignal::new(format!("{:.0}", base_percent(spec_id.get_untracked())));
+    let error = RwSignal::new(None::<String>);
+
+    let options = MODIFIER_SPECS
+        .iter()
+        .map(|modifier_spec| {
+            view! {
+                <option
+                    value=modifier_spec.id.0.to_string()
+                    selected=move || spec_id.get() == modifier_spec.id
+                >
+                    {modifier_spec.name}
+                </option>
+            }
+        })
+        .collect_view();
+    let select_spec = move |event| {
+        let Ok(id) = event_target_value(&event).parse() else {
+            return;
+        };
+        let new_spec = ModifierSpecId(id);
+        spec_id.set(new_spec);
+        draft.set(format!("{:.0}", base_percent(new_spec)));
+    };
+    let add = move |_| {
+        let spec_id = spec_id.get_untracked();
+        let magnitude = draft
+            .get_untracked()
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| MagnitudeError::NotANumber)
+            .map(|percent| percent / 100.0)
+            .and_then(|magnitude| {
+                spec_id
+                    .spec()
+                    .ok_or(MagnitudeError::UnknownSpec(spec_id))?
+                    .validate_magnitude(magnitude)?;
+                Ok(magnitude)
+            });
+        let magnitude = match magnitude {
+            Ok(magnitude) => magnitude,
+            Err(magnitude_error) => {
+                error.set(Some(magnitude_error.to_string()));
+                return;
+            }
+        };
+        match GlobalState::add_pinned_modifier(state, person_id, spec_id, Some(magnitude)) {
+            Some(modifier_id) => {
+                handle.close();
+                show_added_modifier_toast(state, person_id, spec_id, modifier_id);
+            }
+            None => error.set(Some(
+                "Could not add the modifier, see the console".to_string(),
+            )),
+        }
+    };
+
+    view! {
+        <p>
+            {move || view! { <Icon icon=spec_id.get().icon() /> }}
+            " "
+            <select on:change=select_spec>{options}</select>
+            " "
+            <input
+                type="number"
+                prop:value=draft
+                on:input=move |event| draft.set(event_target_value(&event))
+            />
+            "%"
+        </p>
+        {move || error.get().map(|error| view! { <p class=styles::ERROR>{error}</p> })}
+        <button on:click=move |_| handle.close()>Cancel</button>
+        <button on:click=add>Add</button>
+    }
+}
+
+/// Pauses or resumes the weekly tick of a person.
+#[component]
+fn PauseToggle(city_id: CityId, person_id: PersonId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let population = GlobalState::city(state, city_id)?.population();
+    let paused = Population::person(population.into(), person_id)?.paused();
+
+    Ok(view! {
+        <button
+            disabled=read_only.0
+            on:click=move |_| GlobalState::set_person_paused(state, person_id, !paused.get_untracked())
+        >
+            {move || if paused.get() { "Resume" } else { "Pause" }}
+        </button>
+    })
+}
+
+/// Move a person to the trash, announcing it and offering to restore them in a toast.
+/// Removing is undone from the toast or the trash, so it does not ask first.
+fn remove_person(
+    state: Store<GlobalState>,
+    person_id: PersonId,
+    announcement: RwSignal<String>,
+) -> Result<(), SimError> {
+    let city_id = state
+        .read_untracked()
+        .city_of(person_id)
+        .ok_or(SimError::PersonNotFound(person_id))?;
+    GlobalState::trash_person(state, person_id)?;
+    announcement.set(format!(
+        "Person {} removed. They can be restored from the trash.",
+        person_id
+    ));
+    show_undo_toast(format!("Person {person_id} removed."), move || {
+        // Fails if the person was purged from the trash in the meantime.
+        GlobalState::restore_person(state, city_id, person_id)
+            .map_err(|error| format!("Could not restore person {person_id}: {error}"))
+    });
+    Ok(())
+}
+
+/// Moves a person to the trash and clears the selection.
+#[component]
+fn RemovePersonButton(person_id: PersonId) -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let selection = expect_context::<Selection>();
+    let announcement = expect_context::<LiveAnnouncement>().0;
+    let error = RwSignal::new(None::<String>);
+    let remove = move |_| match remove_person(state, person_id, announcement) {
+        Ok(()) => {
+            error.set(None);
+            selection.person.set(None);
+        }
+        Err(sim_error) => error.set(Some(format!("Could not remove: {sim_error}"))),
+    };
+
+    view! {
+        <Show when=move || !read_only.0>
+            <button on:click=remove>Remove</button>
+            {move || error.get().map(|error| view! { <span class=styles::ERROR>{error}</span> })}
+        </Show>
+    }
+}
+
+/// Lists the removed people of a city, who can be restored until they are purged.
+#[component]
+fn TrashPanel(city_id: CityId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let trash = GlobalState::city(state, city_id)?.population().trash();
+    let retention_weeks = state.config().trash_retention_weeks();
+    let error = RwSignal::new(None::<String>);
+    let entries = move || {
+        let week = state.week().get();
+        trash
+            .read()
+            .iter()
+            .map(|(person, removed_week)| {
+                let person_id = person.id;
+                let weeks_left = (removed_week + retention_weeks.get()).saturating_sub(week);
+                view! {
+                    <li>
+                        {format!("Person {person_id} ({weeks_left} weeks left) ")}
+                        <button
+                            disabled=read_only.0
+                            on:click=move |_| {
+                                let result = GlobalState::restore_person(state, city_id, person_id);
+                                error.set(
+                                    result
+                                        .err()
+                                        .map(|sim_error| format!("Could not restore: {sim_error}")),
+                                );
+                            }
+                        >
+                            Restore
+                        </button>
+                    </li>
+                }
+            })
+            .collect_view()
+    };
+
+    Ok(view! {
+        <Show when=move || !trash.read().is_empty()>
+            <details>
+                <summary>{move || format!("Trash ({})", trash.read().len())}</summary>
+                <ul>{entries}</ul>
+                {move || error.get().map(|error| view! { <p class=styles::ERROR>{error}</p> })}
+            </details>
+        </Show>
+    })
+}
+
+/// Moves a person to another city and follows them there.
+#[component]
+fn PersonMigration(city_id: CityId, person_id: PersonId) -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let selection = expect_context::<Selection>();
+    let error = RwSignal::new(None::<String>);
+    let other_cities = move || {
+        state
+            .cities()
+            .iter_unkeyed()
+            .filter(|city| city.id().get() != city_id)
+            .map(|city| {
+                let to_city = city.id().get();
+                let migrate = move |_| match GlobalState::migrate_person(
+                    state, person_id, city_id, to_city,
+                ) {
+                    Ok(()) => selection.city.set(to_city),
+                    Err(sim_error) => error.set(Some(format!("Could not move: {sim_error}"))),
+                };
+                view! { <button on:click=migrate>"Move to " {city.name().get()}</button> }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <Show when=move || !read_only.0>
+            {other_cities}
+            {move || error.get().map(|error| view! { <span class=styles::ERROR>{error}</span> })}
+        </Show>
+    }
+}
+
+/// Shows the notes of a person and edits them in a draft that is only written on save.
+#[component]
+pub fn PersonNotes(city_id: CityId, person_id: PersonId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let population = GlobalState::city(state, city_id)?.population();
+    let notes = Population::person(population.into(), person_id)?.notes();
+    let draft = RwSignal::new(None::<String>);
+
+    let start_editing = move |_| draft.set(Some(notes.get()));
+    let save = move |_| {
+        if let Some(text) = draft.get() {
+            GlobalState::set_person_notes(state, person_id, text);
+        }
+        draft.set(None);
+    };
+    let cancel = move |_| draft.set(None);
+
+    Ok(view! {
+        <Show
+            when=move || draft.read().is_some()
+            fallback=move || {
+                view! {
+                    <div>{move || render_notes(&notes.read())}</div>
+                    <Show when=move || !read_only.0>
+                        <button on:click=start_editing>Edit</button>
+                    </Show>
+                }
+            }
+        >
+            <textarea
+                prop:value=move || draft.get().unwrap_or_default()
+                on:input=move |event| draft.set(Some(event_target_value(&event)))
+            />
+            <button on:click=save>Save</button>
+            <button on:click=cancel>Cancel</button>
+        </Show>
+    })
+}
+
+/// An inline span of notes text.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NoteSpan {
+    Plain(String),
+    Bold(String),
+    Italic(String),
+}
+
+/// Split notes into lines of spans, supporting `**bold**` and `*italic*`.
+/// Markers without a closing counterpart are kept as plain text.
+pub fn parse_notes(text: &str) -> Vec<Vec<NoteSpan>> {
+    text.lines()
+        .map(|line| {
+            let mut spans = Vec::new();
+            let mut plain = String::new();
+            let mut rest = line;
+
+            while let Some(start) = rest.find('*') {
+                plain.push_str(&rest[..start]);
+                let marker = if rest[start..].starts_with("**") {
+                    "**"
+                } else {
+                    "*"
+                };
+                let inner = &rest[start + marker.len()..];
+
+                match inner.find(marker) {
+                    Some(end) if end > 0 => {
+                        if !plain.is_empty() {
+                            spans.push(NoteSpan::Plain(std::mem::take(&mut plain)));
+                        }
+                        let content = inner[..end].to_string();
+                        spans.push(if marker == "**" {
+                            NoteSpan::Bold(content)
+                        } else {
+                            NoteSpan::Italic(content)
+                        });
+                        rest = &inner[end + marker.len()..];
+                    }
+                    _ => {
+                        plain.push_str(marker);
+                        rest = inner;
+                    }
+                }
+            }
+
+            plain.push_str(rest);
+            if !plain.is_empty() {
+                spans.push(NoteSpan::Plain(plain));
+            }
+            spans
+        })
+        .collect()
+}
+
+fn render_notes(text: &str) -> impl IntoView {
+    parse_notes(text)
+        .into_iter()
+        .map(|line| {
+            let spans = line
+                .into_iter()
+                .map(|span| match span {
+                    NoteSpan::Plain(text) => text.into_any(),
+                    NoteSpan::Bold(text) => view! { <strong>{text}</strong> }.into_any(),
+                    NoteSpan::Italic(text) => view! { <em>{text}</em> }.into_any(),
+                })
+                .collect_view();
+            view! { {spans} <br /> }
+        })
+        .collect_view()
+}
+
+/// The number of weeks of modifier snapshots kept per person.
+pub const MODIFIER_HISTORY_WEEKS: usize = 52;
+
+/// The kinds and magnitudes of a person's modifiers as of one week.
+pub type ModifierSnapshot = Box<[(ModifierSpecId, f32)]>;
+
+/// The last [`MODIFIER_HISTORY_WEEKS`] snapshots of a person's modifiers, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifierHistory {
+    /// The week in which the person was created.
+    first_week: u64,
+    snapshots: VecDeque<(u64, ModifierSnapshot)>,
+}
+
+impl ModifierHistory {
+    fn new(first_week: u64, modifiers: &[HappinessModifier]) -> Self {
+        let mut history = Self {
+            first_week,
+            snapshots: VecDeque::new(),
+        };
+        history.record(first_week, Self::snapshot(modifiers));
+        history
+    }
+
+    fn snapshot(modifiers: &[HappinessModifier]) -> ModifierSnapshot {
+        modifiers
+            .iter()
+            .map(|modifier| (modifier.spec, modifier.current_magnitude() as f32))
+            .collect()
+    }
+
+    fn record(&mut self, week: u64, snapshot: ModifierSnapshot) {
+        if self.snapshots.len() == MODIFIER_HISTORY_WEEKS {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((week, snapshot));
+    }
+
+    /// The oldest week that still has a snapshot.
+    pub fn oldest_week(&self) -> u64 {
+        self.snapshots
+            .front()
+            .map_or(self.first_week, |(week, _)| *week)
+    }
+
+    /// The modifiers as of `week`, or `None` if the person did not exist yet
+    /// or the week is older than the kept history.
+    ///
+    /// Weeks without their own snapshot, e.g. while paused, show the one before.
+    pub fn at(&self, week: u64) -> Option<&[(ModifierSpecId, f32)]> {
+        if week < self.oldest_week() {
+            return None;
+        }
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|(snapshot_week, _)| *snapshot_week <= week)
+            .map(|(_, snapshot)| &**snapshot)
+    }
+}
+
+#[derive(Debug, Clone, Store, Serialize, Deserialize)]
+pub struct Happiness {
+    #[store(key: HappinessModifierId = |row| row.key())]
+    happiness_modifiers: Vec<HappinessModifier>,
+    /// How much the total changed in the last tick, or `None` if the person was not ticked yet.
+    last_week_delta: Option<f64>,
+    /// Recorded at the end of every tick, for [`ModifierTimeline`].
+    history: ModifierHistory,
+}
+
+impl Happiness {
+    pub fn new_initial(
+        week: u64,
+        initial_modifiers: &[InitialModifier],
+        ids: &mut IdAllocator,
+    ) -> Self {
+        let happiness_modifiers: Vec<_> = initial_modifiers
+            .iter()
+            .map(|initial| initial.instantiate(week, ids))
+            .collect();
+        Self {
+            history: ModifierHistory::new(week, &happiness_modifiers),
+            happiness_modifiers,
+            last_week_delta: None,
+        }
+    }
+
+    /// Counts the added and expired modifiers in `stats`.
+    pub fn finish_week(
+        person: Field<Person>,
+        week: u64,
+        config: &SimConfig,
+        stats: &mut ModifierStats,
+        ids: &mut IdAllocator,
+    ) {
+        if person.paused().get_untracked() {
+            return;
+        }
+        let happiness = person.happiness();
+        let total_before = happiness.read_untracked().current_total();
+        // Expire and decay happiness modifiers. The weekly ones are recomputed every week.
+        // This happens before anything reads the totals of the new week.
+        let mut reissued_since = None;
+        Self::advance_modifiers(
+            &mut person.happiness().happiness_modifiers().write(),
+            config,
+            |expired| {
+                stats.entry(expired.spec).or_default().expired += 1;
+                if expired.spec == ModifierSpecId::DEFAULT && !expired.pinned {
+                    reissued_since = Some(expired.created_week);
+                }
+            },
+        );
+        record_mutation(
+            week,
+            || {
+                format!(
+                    "people[{:?}].happiness.happiness_modifiers",
+                    person.id().get_untracked()
+                )
+            },
+            MutationKind::Update,
+            "Happiness::finish_week",
+        );
+        // The recomputed weekly modifier is the one that expired, so it is not new.
+        if Self::add_happiness_modifier(
+            person.happiness().into(),
+            person.id().get_untracked(),
+            week,
+            reissued_since.unwrap_or(week),
+            ids,
+        ) {
+            stats.entry(ModifierSpecId::DEFAULT).or_default().added += 1;
+        }
+        let total_after = happiness.read_untracked().current_total();
+        happiness
+            .last_week_delta()
+            .set(Some(total_after - total_before));
+        // Taken before writing, the store cannot be read while a write guard is held.
+        let snapshot = ModifierHistory::snapshot(&happiness.happiness_modifiers().read_untracked());
+        happiness.history().write().record(week, snapshot);
+    }
+
+    /// Expire and decay the modifiers by one week, as a tick does. Pinned modifiers only decay.
+    /// `on_expire` is called with each modifier before it is removed.
+    fn advance_modifiers(
+        modifiers: &mut Vec<HappinessModifier>,
+        config: &SimConfig,
+        mut on_expire: impl FnMut(&HappinessModifier),
+    ) {
+        modifiers.retain_mut(|modifier| {
+            let keep = modifier.advance_week(config);
+            if !keep {
+                on_expire(modifier);
+            }
+            keep
+        });
+    }
+
+    /// The totals of the next `weeks` weeks if only expiry and decay changed the modifiers,
+    /// using the same step as the tick. Modifiers that future ticks add are not included.
+    pub fn forecast(&self, weeks: u32, config: &SimConfig) -> Vec<f64> {
+        let mut modifiers = self.happiness_modifiers.clone();
+        (0..weeks)
+            .map(|_| {
+                Self::advance_modifiers(&mut modifiers, config, |_| {});
+                Self::breakdown(
+                    modifiers
+                        .iter()
+                        .map(|modifier| (modifier.spec, modifier.current_magnitude())),
+                )
+                .total
+            })
+            .collect()
+    }
+
+    /// The sum of all modifiers, computed from plain values.
+    pub fn current_total(&self) -> f64 {
+        Self::breakdown(
+            self.happiness_modifiers
+                .iter()
+                .map(|modifier| (modifier.spec, modifier.current_magnitude())),
+        )
+        .total
+    }
+
+    /// The total and per-category happiness of modifiers given as kinds and magnitudes.
+    /// Works on plain values, so previews can compute it without touching the store.
+    pub fn breakdown(
+        modifiers: impl IntoIterator<Item = (ModifierSpecId, f64)>,
+    ) -> HappinessBreakdown {
+        let mut breakdown = HappinessBreakdown::default();
+        for (spec_id, magnitude) in modifiers {
+            breakdown.total += magnitude;
+            if let Some(spec) = spec_id.spec() {
+                *breakdown.by_category.entry(spec.category).or_default() += magnitude;
+            }
+        }
+        breakdown
+    }
+
+    /// Add a modifier by hand. It is pinned, so it does not expire.
+    pub fn add_pinned_modifier(
+        this: Field<Self>,
+        spec: ModifierSpecId,
+        magnitude: Option<f64>,
+        week: u64,
+        ids: &mut IdAllocator,
+    ) -> HappinessModifierId {
+        let modifier = ModifierTemplate {
+            spec,
+            magnitude,
+            pinned: true,
+        }
+        .instantiate(week, ids);
+        let modifier_id = modifier.id;
+        this.happiness_modifiers().write().push(modifier);
+        record_mutation(
+            week,
+            || format!("happiness_modifiers[{modifier_id:?}]"),
+            MutationKind::Insert,
+            "Happiness::add_pinned_modifier",
+        );
+        modifier_id
+    }
+
+    /// Remove a modifier in one write. Returns false if the modifier does not exist.
+    pub fn remove_modifier(this: Field<Self>, modifier_id: HappinessModifierId) -> bool {
+        let modifiers = this.happiness_modifiers();
+        let Some(index) = modifiers
+            .read_untracked()
+            .iter()
+            .position(|modifier| modifier.id == modifier_id)
+        else {
+            return false;
+        };
+        modifiers.write().remove(index);
+        true
+    }
+
+    /// Move a modifier to the given index in one write. Returns false if the modifier does not exist.
+    pub fn move_modifier(
+        this: Field<Self>,
+        modifier_id: HappinessModifierId,
+        new_index: usize,
+    ) -> bool {
+        let modifiers = this.happiness_modifiers();
+        let Some(index) = modifiers
+            .read_untracked()
+            .iter()
+            .position(|modifier| modifier.id == modifier_id)
+        else {
+            return false;
+        };
+        modifiers.update(|modifiers| {
+            let modifier = modifiers.remove(index);
+            modifiers.insert(new_index.min(modifiers.len()), modifier);
+        });
+        true
+    }
+
+    /// Pin or unpin a modifier. Returns false if the modifier does not exist.
+    pub fn set_modifier_pinned(
+        this: Field<Self>,
+        modifier_id: HappinessModifierId,
+        pinned: bool,
+    ) -> bool {
+        let modifiers = this.happiness_modifiers();
+        let Some(index) = modifiers
+            .read_untracked()
+            .iter()
+            .position(|modifier| modifier.id == modifier_id)
+        else {
+            return false;
+        };
+        modifiers.write()[index].pinned = pinned;
+        true
+    }
+
+    /// The sum of all happiness modifiers.
+    pub fn total(this: Field<Self>) -> f64 {
+        Self::sum_modifiers(this, |_, _| {})
+    }
+
+    /// How [`Happiness::total`] comes about, step by step.
+    /// The total is summed by the same code, so the two always agree.
+    pub fn explain(this: Field<Self>) -> Explanation {
+        let mut modifiers = Vec::new();
+        let total = Self::sum_modifiers(this, |modifier, happiness| {
+            let modifier = modifier.read();
+            modifiers.push(ExplainedModifier {
+                id: modifier.id,
+                spec: modifier.spec,
+                happiness,
+                is_override: modifier.magnitude.is_some(),
+            });
+        });
+        let mut by_category = BTreeMap::<_, f64>::new();
+        for modifier in &modifiers {
+            if let Some(spec) = modifier.spec.spec() {
+                *by_category.entry(spec.category).or_default() += modifier.happiness;
+            }
+        }
+        Explanation {
+            modifiers,
+            by_category,
+            total,
+        }
+    }
+
+    /// Sum the happiness of the modifiers in order, calling `visit` with each modifier and its happiness.
+    fn sum_modifiers(
+        this: Field<Self>,
+        mut visit: impl FnMut(Field<HappinessModifier>, f64),
+    ) -> f64 {
+        this.happiness_modifiers()
+            .iter_unkeyed()
+            .map(|modifier| {
+                let modifier = modifier.into();
+                // Only unreadable while the store is written, which no reader of the sum does.
+                let happiness = HappinessModifier::happiness(modifier).unwrap_or_else(|error| {
+                    log::warn!("Leaving a modifier out of the happiness: {error}");
+                    0.0
+                });
+                visit(modifier, happiness);
+                happiness
+            })
+            .sum()
+    }
+
+    /// Override the magnitude of a modifier after validating it against the modifier's spec.
+    pub fn set_modifier_magnitude(
+        this: Field<Self>,
+        modifier_id: HappinessModifierId,
+        magnitude: f64,
+    ) -> Result<(), MagnitudeError> {
+        let modifiers = this.happiness_modifiers();
+        let spec_id = modifiers
+            .read_untracked()
+            .iter()
+            .find(|modifier| modifier.id == modifier_id)
+            .map(|modifier| modifier.spec)
+            .ok_or(SimError::ModifierNotFound(modifier_id))?;
+        spec_id
+            .spec()
+            .ok_or(MagnitudeError::UnknownSpec(spec_id))?
+            .validate_magnitude(magnitude)?;
+
+        if let Some(modifier) = modifiers
+            .write()
+            .iter_mut()
+            .find(|modifier| modifier.id == modifier_id)
+        {
+            // The new magnitude is the one shown, and decays from there.
+            modifier.magnitude = Some(magnitude);
+            modifier.decay_factor = HappinessModifier::undecayed();
+        }
+        Ok(())
+    }
+
+    /// Add the weekly modifier in `week`, dated `created_week`.
+    /// Returns false if the modifier was not added, see [`exceeds_mutation_rate`].
+    pub fn add_happiness_modifier(
+        this: Field<Self>,
+        person_id: PersonId,
+        week: u64,
+        created_week: u64,
+        ids: &mut IdAllocator,
+    ) -> bool {
+        if exceeds_mutation_rate(person_id, "Happiness::add_happiness_modifier") {
+            return false;
+        }
+        let modifier = HappinessModifier::create(ModifierSpecId::DEFAULT, created_week, ids);
+        let modifier_id = modifier.id;
+        this.happiness_modifiers().write().push(modifier);
+        record_mutation(
+            week,
+            || format!("happiness_modifiers[{modifier_id:?}]"),
+            MutationKind::Insert,
+            "Happiness::add_happiness_modifier",
+        );
+        true
+    }
+}
+
+/// The modifiers of the person `person_id` points to.
+/// Another person gets a table of their own, so no row acts on the previous one.
+#[component]
+pub fn HappinessModifierTable(
+    city_id: CityId,
+    #[prop(into)] person_id: Signal<PersonId>,
+) -> impl IntoView {
+    move || {
+        let person_id = person_id.get();
+        view! { <PersonModifierTable city_id person_id /> }
+    }
+}
+
+#[component]
+fn PersonModifierTable(city_id: CityId, person_id: PersonId) -> Result<impl IntoView, SimError> {
+    let state = use_global_state();
+    let population = GlobalState::city(state, city_id)?.population();
+    let person = Population::person(population.into(), person_id)?;
+    let happiness = person.happiness();
+    let rows = Signal::derive(move || {
+        happiness
+            .happiness_modifiers()
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    });
+    let columns = vec![
+        EntityColumn::new("Kind", |modifier: Field<HappinessModifier>| {
+            let spec = move || modifier.spec().get().spec();
+            view! {
+                <span title=move || spec().map_or("", |spec| spec.description)>
+                    {move || spec().map_or("Unknown", |spec| spec.name)}
+                    {move || spec().is_some_and(|spec| spec.decays).then_some(" (decaying)")}
+                </span>
+            }
+        })
+        .sortable(|modifier| {
+            modifier
+                .spec()
+                .get()
+                .spec()
+                .map_or(0.0, |spec| f64::from(spec.priority))
+        }),
+        EntityColumn::new("Category", |modifier: Field<HappinessModifier>| {
+            move || {
+                modifier
+                    .spec()
+                    .get()
+                    .spec()
+                    .map_or("Unknown", |spec| spec.category.display_name())
+            }
+        }),
+        EntityColumn::new("Happiness", move |modifier: Field<HappinessModifier>| {
+            view! {
+                <HappinessModifierTableEntry person_id happiness_modifier=modifier />
+            }
+        })
+        .sortable(|modifier| HappinessModifier::happiness(modifier).unwrap_or(0.0)),
+    ];
+
+    let pinned = Callback::new(|modifier: Field<HappinessModifier>| modifier.pinned().get());
+    provide_context(DraggedModifier(RwSignal::new(None)));
+
+    Ok(view! {
+        <AddModifierForm person_id />
+        <EntityTable rows columns pinned row_classes=styles::MODIFIER_ROW />
+    })
+}
+
+/// The pinned modifier being dragged to a new position, if any.
+#[derive(Debug, Clone, Copy)]
+struct DraggedModifier(RwSignal<Option<HappinessModifierId>>);
+
+/// Adds a pinned modifier of the chosen kind by hand.
+#[component]
+fn AddModifierForm(person_id: PersonId) -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let spec = RwSignal::new(ModifierSpecId::DEFAULT);
+    let options = MODIFIER_SPECS
+        .iter()
+        .map(|modifier_spec| {
+            view! {
+                <option
+                    value=modifier_spec.id.0.to_string()
+                    selected=move || spec.get() == modifier_spec.id
+                >
+                    {modifier_spec.name}
+                </option>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <Show when=move || !read_only.0>
+            <select on:change=move |event| {
+                if let Ok(id) = event_target_value(&event).parse() {
+                    spec.set(ModifierSpecId(id));
+                }
+            }>{options.clone()}</select>
+            <button on:click=move |_| {
+                let spec = spec.get_untracked();
+                if let Some(modifier_id) = GlobalState::add_pinned_modifier(
+                    state,
+                    person_id,
+                    spec,
+                    None,
+                ) {
+                    show_added_modifier_toast(state, person_id, spec, modifier_id);
+                }
+            }>"Add pinned modifier"</button>
+        </Show>
+    }
+}
+
+/// Describes a modifier to add to many people at once.
+#[derive(Debug, Clone, Copy)]
+pub struct ModifierTemplate {
+    pub spec: ModifierSpecId,
+    /// Overrides the base magnitude of the spec.
+    pub magnitude: Option<f64>,
+    pub pinned: bool,
+}
+
+impl ModifierTemplate {
+    /// A new modifier with a fresh id.
+    pub fn instantiate(self, week: u64, ids: &mut IdAllocator) -> HappinessModifier {
+        let mut modifier = HappinessModifier::create(self.spec, week, ids);
+        modifier.magnitude = self.magnitude;
+        modifier.pinned = self.pinned;
+        modifier
+    }
+}
+
+/// Adds a pinned modifier to everyone in the city matching the population table's filter.
+#[component]
+fn BulkModifierForm(city_id: CityId) -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let filter = expect_context::<Store<UiPreferences>>().modifier_filter();
+    let announcement = expect_context::<LiveAnnouncement>().0;
+    let spec = RwSignal::new(ModifierSpecId::DEFAULT);
+    let options = MODIFIER_SPECS
+        .iter()
+        .map(|modifier_spec| {
+            view! {
+                <option
+                    value=modifier_spec.id.0.to_string()
+                    selected=move || spec.get() == modifier_spec.id
+                >
+                    {modifier_spec.name}
+                </option>
+            }
+        })
+        .collect_view();
+    let apply = move |_| {
+        let spec = spec.get_untracked();
+        let filter = filter.get_untracked();
+        let template = ModifierTemplate {
+            spec,
+            magnitude: None,
+            pinned: true,
+        };
+        let name = spec.spec().map_or("Unknown", |spec| spec.name);
+        match GlobalState::apply_modifier_to_matching(
+            state,
+            city_id,
+            |person| filter.matches(person),
+            template,
+        ) {
+            Ok(count) => announcement.set(format!("Added {name} to {count} people")),
+            Err(error) => announcement.set(format!("Could not add {name}: {error}")),
+        }
+    };
+
+    view! {
+        <Show when=move || !read_only.0>
+            <select on:change=move |event| {
+                if let Ok(id) = event_target_value(&event).parse() {
+                    spec.set(ModifierSpecId(id));
+                }
+            }>{options.clone()}</select>
+            <button on:click=apply>"Pin to everyone shown"</button>
+        </Show>
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HappinessBreakdown {
+    pub total: f64,
+    pub by_category: BTreeMap<ModifierCategory, f64>,
+}
+
+/// The steps of [`Happiness::explain`]. Modifiers neither stack nor are capped or clamped,
+/// so the total is the plain sum of the modifiers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    /// Every modifier in order with the happiness it adds.
+    pub modifiers: Vec<ExplainedModifier>,
+    /// The sums per category, for information. Modifiers of unknown kinds are in no category.
+    pub by_category: BTreeMap<ModifierCategory, f64>,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainedModifier {
+    pub id: HappinessModifierId,
+    pub spec: ModifierSpecId,
+    pub happiness: f64,
+    /// Whether the magnitude is overridden rather than the base magnitude of the spec.
+    pub is_override: bool,
+}
+
+#[derive(Debug, Clone, Store, Serialize, Deserialize)]
+pub struct HappinessModifier {
+    id: HappinessModifierId,
+    spec: ModifierSpecId,
+    /// Overrides the base magnitude of the spec.
+    magnitude: Option<f64>,
+    /// The number of weeks until this modifier expires, or `None` if it is permanent.
+    remaining_weeks: Option<u32>,
+    /// The week in which this modifier was created.
+    /// The weekly modifier keeps the week of the first one when it is recomputed.
+    created_week: u64,
+    /// Set for modifiers added by hand. Pinned modifiers do not expire, but still decay.
+    pinned: bool,
+    /// The share of the magnitude left after decay, one until the modifier first decays.
+    /// Kept apart from the override, so a decayed modifier still counts as not overridden.
+    #[serde(default = "HappinessModifier::undecayed")]
+    decay_factor: f64,
+}
+
+/// Shown and parsed as a plain number, like [`PersonId`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u64")]
+pub struct HappinessModifierId(u64);
+
+impl fmt::Display for HappinessModifierId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<u64> for HappinessModifierId {
+    type Error = ParseIdError;
+
+    fn try_from(id: u64) -> Result<Self, ParseIdError> {
+        if id == 0 {
+            return Err(ParseIdError::Zero);
+        }
+        Ok(Self(id))
+    }
+}
+
+impl FromStr for HappinessModifierId {
+    type Err = ParseIdError;
+
+    fn from_str(text: &str) -> Result<Self, ParseIdError> {
+        let id: u64 = text.parse().map_err(|_| ParseIdError::NotANumber)?;
+        id.try_into()
+    }
+}
+
+impl HappinessModifier {
+    pub fn create(spec: ModifierSpecId, created_week: u64, ids: &mut IdAllocator) -> Self {
+        Self {
+            id: ids.modifier_id(),
+            spec,
+            magnitude: None,
+            remaining_weeks: spec.spec().and_then(|spec| spec.default_duration),
+            created_week,
+            pinned: false,
+            decay_factor: Self::undecayed(),
+        }
+    }
+
+    fn undecayed() -> f64 {
+        1.0
+    }
+
+    /// The magnitude of this modifier, taking the override and the decay into account.
+    pub fn current_magnitude(&self) -> f64 {
+        let magnitude = self
+            .magnitude
+            .unwrap_or_else(|| self.spec.spec().map_or(0.0, |spec| spec.base_magnitude));
+        magnitude * self.decay_factor
+    }
+
+    /// Count down the duration, unless pinned, and apply decay.
+    /// Returns false if the modifier expired or decayed away and should be removed.
+    pub fn advance_week(&mut self, config: &SimConfig) -> bool {
+        if let Some(remaining_weeks) = self.remaining_weeks.as_mut().filter(|_| !self.pinned) {
+            *remaining_weeks = remaining_weeks.saturating_sub(1);
+            if *remaining_weeks == 0 {
+                return false;
+            }
+        }
+
+        if self.spec.spec().is_some_and(|spec| spec.decays) {
+            self.decay_factor *= 1.0 - config.modifier_decay_rate;
+            if self.current_magnitude().abs() < config.modifier_decay_threshold {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Fails if the modifier cannot be read, e.g. while its store is being written.
+    pub fn happiness(this: Field<Self>) -> Result<f64, SimError> {
+        let magnitude = *this
+            .magnitude()
+            .try_read()
+            .ok_or(SimError::StoreReadUnavailable)?;
+        let decay_factor = *this
+            .decay_factor()
+            .try_read()
+            .ok_or(SimError::StoreReadUnavailable)?;
+        let magnitude = match magnitude {
+            Some(magnitude) => magnitude,
+            None => this
+                .spec()
+                .try_read()
+                .ok_or(SimError::StoreReadUnavailable)?
+                .spec()
+                .map_or(0.0, |spec| spec.base_magnitude),
+        };
+        Ok(magnitude * decay_factor)
+    }
+}
+
+impl Entity for HappinessModifier {
+    type Key = HappinessModifierId;
+
+    fn key(&self) -> HappinessModifierId {
+        self.id
+    }
+
+    fn entity_ref(key: HappinessModifierId) -> EntityRef {
+        EntityRef::HappinessModifier(key)
+    }
+}
+
+/// Identifies a [`ModifierSpec`] in [`MODIFIER_SPECS`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct ModifierSpecId(u32);
+
+impl ModifierSpecId {
+    pub const DEFAULT: Self = Self(1);
+    pub const NOVELTY: Self = Self(2);
+
+    /// The spec with this id, or `None` if the id is not registered.
+    pub fn spec(self) -> Option<&'static ModifierSpec> {
+        MODIFIER_SPECS.iter().find(|spec| spec.id == self)
+    }
+
+    /// The icon of the spec, or the generic icon if the id is not registered.
+    pub fn icon(self) -> IconId {
+        self.spec().map_or(IconId::GENERIC, |spec| spec.icon)
+    }
+}
+
+/// Describes a kind of happiness modifier.
+#[derive(Debug)]
+pub struct ModifierSpec {
+    pub id: ModifierSpecId,
+    pub name: &'static str,
+    pub base_magnitude: f64,
+    /// The smallest magnitude the modifier may be edited to.
+    pub min_magnitude: f64,
+    /// The largest magnitude the modifier may be edited to.
+    pub max_magnitude: f64,
+    pub category: ModifierCategory,
+    /// Higher priorities are more important.
+    pub priority: u32,
+    /// The number of weeks a new modifier lasts, or `None` if it is permanent.
+    pub default_duration: Option<u32>,
+    /// Whether the magnitude shrinks every week by [`SimConfig`]'s decay rate.
+    pub decays: bool,
+    pub description: &'static str,
+    pub icon: IconId,
+}
+
+impl ModifierSpec {
+    /// Whether a modifier of this kind may be set to `magnitude`.
+    pub fn validate_magnitude(&self, magnitude: f64) -> Result<(), MagnitudeError> {
+        if !magnitude.is_finite() {
+            return Err(MagnitudeError::NotFinite);
+        }
+        if !(self.min_magnitude..=self.max_magnitude).contains(&magnitude) {
+            return Err(MagnitudeError::OutOfRange {
+                min: self.min_magnitude,
+                max: self.max_magnitude,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum ModifierCategory {
+    General,
+}
+
+impl ModifierCategory {
+    pub const ALL: &[Self] = &[Self::General];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::General => "General",
+        }
+    }
+}
+
+/// All known happiness modifier kinds.
+/// Adding a kind only requires a new entry here.
+pub static MODIFIER_SPECS: &[ModifierSpec] = &[
+    ModifierSpec {
+        id: ModifierSpecId::DEFAULT,
+        name: "Default",
+        base_magnitude: 0.5,
+        min_magnitude: 0.0,
+        max_magnitude: 1.0,
+        category: ModifierCategory::General,
+        priority: 0,
+        default_duration: Some(1),
+        decays: false,
+        description: "The baseline happiness everyone gets every week.",
+        icon: IconId("smile"),
+    },
+    ModifierSpec {
+        id: ModifierSpecId::NOVELTY,
+        name: "Novelty",
+        base_magnitude: 0.3,
+        min_magnitude: 0.0,
+        max_magnitude: 1.0,
+        category: ModifierCategory::General,
+        priority: 0,
+        default_duration: None,
+        decays: true,
+        description: "The joy of something new, which wears off a little every week.",
+        icon: IconId::GENERIC,
+    },
+];
+
+/// Names an icon in [`ICONS`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IconId(pub &'static str);
+
+impl IconId {
+    /// Shown for ids without an entry in [`ICONS`].
+    pub const GENERIC: Self = Self("generic");
+
+    /// The SVG path of this icon in a 16×16 view box, or of the generic icon if it is not registered.
+    pub fn path(self) -> &'static str {
+        let find = |icon: Self| ICONS.iter().find(|(id, _)| *id == icon);
+        find(self)
+            .or_else(|| find(Self::GENERIC))
+            .map_or("", |(_, path)| path)
+    }
+}
+
+/// All icons with their SVG paths in a 16×16 view box, filled with the even-odd rule.
+/// Adding an icon only requires a new entry here.
+pub static ICONS: &[(IconId, &str)] = &[
+    (IconId::GENERIC, "M8 2a6 6 0 1 0 0 12A6 6 0 0 0 8 2z"),
+    (
+        IconId("smile"),
+        "M8 1a7 7 0 1 0 0 14A7 7 0 0 0 8 1zM5.5 5a1 1 0 1 1 0 2 1 1 0 0 1 0-2zm5 0a1 1 0 1 1 0 2 \
+         1 1 0 0 1 0-2zM4.5 9h7a3.5 3.5 0 0 1-7 0z",
+    ),
+];
+
+/// An inline SVG icon in the current text color.
+#[component]
+pub fn Icon(icon: IconId) -> impl IntoView {
+    view! {
+        <svg viewBox="0 0 16 16" width="1em" height="1em" fill="currentColor" aria-hidden="true">
+            <path fill-rule="evenodd" d=icon.path() />
+        </svg>
+    }
+}
+
+/// Every registered icon with its name, to check them visually.
+#[component]
+fn IconGallery() -> impl IntoView {
+    view! {
+        <fieldset>
+            <legend>Icons</legend>
+            {ICONS
+                .iter()
+                .map(|&(icon, _)| {
+                    view! {
+                        <span style="margin-right: 1em">
+                            <Icon icon />
+                            " "
+                            {icon.0}
+                        </span>
+                    }
+                })
+                .collect_view()}
+        </fieldset>
+    }
+}
+
+#[component]
+pub fn HappinessModifierTableEntry(
+    person_id: PersonId,
+    #[prop(into)] happiness_modifier: Field<HappinessModifier>,
+) -> impl IntoView {
+    let state = use_global_state();
+    let read_only = expect_context::<ReadOnly>();
+    let happiness = Signal::derive(move || {
+        let _scope = ReadScope::enter();
+        HappinessModifier::happiness(happiness_modifier)
+    });
+    let is_new =
+        Signal::derive(move || happiness_modifier.created_week().get() == state.week().get());
+    // Rows are keyed by id, so the id of this entry never changes.
+    let modifier_id = happiness_modifier.read_untracked().key();
+    let spec = happiness_modifier.spec();
+    let pinned = happiness_modifier.pinned();
+    let error = RwSignal::new(None::<String>);
+    let toggle_pinned = move |_| {
+        let result = GlobalState::set_modifier_pinned(
+            state,
+            person_id,
+            modifier_id,
+            !pinned.get_untracked(),
+        );
+        error.set(result.err().map(|sim_error| sim_error.to_string()));
+    };
+
+    // Pinned modifiers can be dragged onto each other to reorder them.
+    let dragged = expect_context::<DraggedModifier>().0;
+    let is_drop_target = RwSignal::new(false);
+    let accepts_drop = move || {
+        pinned.get_untracked()
+            && dragged
+                .get_untracked()
+                .is_some_and(|dragged| dragged != modifier_id)
+    };
+    let start_drag = move |event: web_sys::DragEvent| {
+        if let Some(data_transfer) = event.data_transfer() {
+            // Some browsers only start a drag with data attached.
+            let _ = data_transfer.set_data("text/plain", &modifier_id.to_string());
+        }
+        dragged.set(Some(modifier_id));
+    };
+    let drag_over = move |event: web_sys::DragEvent| {
+        if accepts_drop() {
+            event.prevent_default();
+            is_drop_target.set(true);
+        }
+    };
+    let finish_drop = move |event: web_sys::DragEvent| {
+        event.prevent_default();
+        is_drop_target.set(false);
+        if let Some(dragged_id) = dragged.get_untracked() {
+            GlobalState::move_modifier_to(state, person_id, dragged_id, modifier_id);
+        }
+        dragged.set(None);
+    };
+    // The text being edited, in percent.
+    let draft = RwSignal::new(None::<String>);
+
+    let start_editing = move |_| {
+        if !read_only.0 {
+            if let Ok(happiness) = happiness.get_untracked() {
+                draft.set(Some(format!("{:.0}", happiness * 100.0)));
+            }
+        }
+    };
+    let commit = move || {
+        let Some(text) = draft.get_untracked() else {
+            return;
+        };
+        let result = text
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| MagnitudeError::NotANumber)
+            .and_then(|percent| {
+                // Validated against the current state, the modifier may have been removed by a tick.
+                GlobalState::set_modifier_magnitude(state, person_id, modifier_id, percent / 100.0)
+            });
+        match result {
+            Ok(()) => {
+                draft.set(None);
+                error.set(None);
+            }
+            Err(magnitude_error) => error.set(Some(magnitude_error.to_string())),
+        }
+    };
+    let revert = move || {
+        draft.set(None);
+        error.set(None);
+    };
+
+    view! {
+        <span
+            class={styles::MODIFIER_ENTRY}
+            {..styles::toggle(styles::MODIFIER_ENTRY_NEW, move || is_new.get())}
+            {..styles::toggle(styles::MODIFIER_ENTRY_DROP_TARGET, move || is_drop_target.get())}
+            draggable=move || (pinned.get() && !read_only.0).to_string()
+            on:dragstart=start_drag
+            on:dragover=drag_over
+            on:dragleave=move |_| is_drop_target.set(false)
+            on:drop=finish_drop
+            // Also fires when dropped outside the table, which cancels the move.
+            on:dragend=move |_| dragged.set(None)
+        >
+            <button
+                title=move || if pinned.get() { "Unpin" } else { "Pin" }
+                style:opacity=move || if pinned.get() { "1" } else { "0.4" }
+                disabled=read_only.0
+                on:click=toggle_pinned
+            >
+                "📌"
+            </button>
+            {move || view! { <Icon icon=spec.get().icon() /> }}
+            " "
+            <Show
+                when=move || draft.read().is_some()
+                fallback=move || {
+                    view! {
+                        <span on:click=start_editing>
+                            {move || {
+                                happiness
+                                    .get()
+                                    .map_or_else(
+                                        |error| error.to_string(),
+                                        |happiness| format!("{:.0}%", happiness * 100.0),
+                                    )
+                            }}
+                        </span>
+                    }
+                }
+            >
+                <input
+                    type="number"
+                    prop:value=move || draft.get().unwrap_or_default()
+                    on:input=move |event| draft.set(Some(event_target_value(&event)))
+                    on:keydown=move |event| match event.key().as_str() {
+                        "Enter" => commit(),
+                        "Escape" => revert(),
+                        _ => {}
+                    }
+                    on:blur=move |_| commit()
+                />
+                "%"
+                {move || error.get().map(|error| view! { <span class=styles::ERROR>{error}</span> })}
+            </Show>
+        </span>
+    }
+}
+
+/// Why an operation on the simulation failed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimError {
+    CityNotFound(CityId),
+    PersonNotFound(PersonId),
+    /// The index of a population points at another person than the one looked up.
+    IndexCorrupt(PersonId),
+    NotInCity {
+        person_id: PersonId,
+        city_id: CityId,
+    },
+    NotInTrash {
+        person_id: PersonId,
+        city_id: CityId,
+    },
+    /// A person cannot migrate to the city they live in.
+    SameCity(CityId),
+    /// The modifier is not one of the person's, e.g. because it expired.
+    ModifierNotFound(HappinessModifierId),
+    ReadOnly,
+    WriteDuringRead,
+    /// A field could not be read, because it is being written or its store was disposed.
+    StoreReadUnavailable,
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CityNotFound(city_id) => write!(f, "{city_id:?} does not exist"),
+            Self::PersonNotFound(person_id) => write!(f, "person {person_id} does not exist"),
+            Self::IndexCorrupt(person_id) => {
+                write!(f, "the index of person {person_id} is out of sync")
+            }
+            Self::NotInCity { person_id, city_id } => {
+                write!(f, "person {person_id} does not live in {city_id:?}")
+            }
+            Self::NotInTrash { person_id, city_id } => {
+                write!(f, "person {person_id} is not in the trash of {city_id:?}")
+            }
+            Self::SameCity(city_id) => write!(f, "already living in {city_id:?}"),
+            Self::ModifierNotFound(modifier_id) => {
+                write!(f, "modifier {modifier_id} does not exist")
+            }
+            Self::ReadOnly => write!(f, "read-only mode"),
+            Self::WriteDuringRead => write!(f, "cannot be changed while the page renders"),
+            Self::StoreReadUnavailable => write!(f, "cannot be read right now"),
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MagnitudeError {
+    NotANumber,
+    NotFinite,
+    OutOfRange {
+        min: f64,
+        max: f64,
+    },
+    UnknownSpec(ModifierSpecId),
+    RateLimited,
+    /// The modifier could not be looked up or changed at all.
+    Sim(SimError),
+}
+
+impl From<SimError> for MagnitudeError {
+    fn from(error: SimError) -> Self {
+        Self::Sim(error)
+    }
+}
+
+impl fmt::Display for MagnitudeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotANumber => write!(f, "not a number"),
+            Self::NotFinite => write!(f, "must be finite"),
+            Self::OutOfRange { min, max } => write!(
+                f,
+                "must be between {:.0}% and {:.0}%",
+                min * 100.0,
+                max * 100.0
+            ),
+            Self::UnknownSpec(spec_id) => write!(f, "unknown modifier kind {spec_id:?}"),
+            Self::RateLimited => write!(f, "changed too often, see the console"),
+            Self::Sim(error) => error.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Drives the simulation without rendering anything, for tests and other crates.
+//!
+//! [`Simulation`] owns the store and its reactive owner, and hands out plain data,
+//! so callers never deal with reactive types. It finishes weeks through the same
+//! [`GlobalState`] functions as the "Finish Week" button.
+//!
+//! A game can be saved as JSON at any week and continued later,
+//! playing out as if it had never stopped:
+//!
+//! ```
+//! use bug::{sim::Simulation, SimConfig};
+//!
+//! let mut simulation = Simulation::new(SimConfig::default(), 7);
+//! let outcomes = simulation.run(3);
+//! assert_eq!(outcomes.last().map(|outcome| outcome.week), Some(3));
+//!
+//! let people = simulation.people();
+//! let first = simulation.person(people[0].id).unwrap();
+//! assert_eq!(first, people[0]);
+//!
+//! let saved = simulation.state_json();
+//! simulation.run(5);
+//!
+//! let mut loaded = Simulation::new(SimConfig::default(), 1);
+//! loaded.load_json(&saved, false).unwrap();
+//! assert_eq!(loaded.people(), people);
+//! loaded.run(5);
+//! assert_eq!(loaded.people(), simulation.people());
+//! ```
+
+use leptos::prelude::*;
+use reactive_stores::Store;
+
+use crate::{
+    reset_mutation_counts, CityId, GlobalState, GlobalStateStoreFields, ModifierSpecId, PersonId,
+    SimConfig, SimRng, WeekOutcome,
+};
+
+/// A game that is advanced by hand, see the [module docs](self).
+pub struct Simulation {
+    owner: Owner,
+    state: Store<GlobalState>,
+}
+
+/// A person as plain data, as of the call that returned it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonSnapshot {
+    pub id: PersonId,
+    pub city_id: CityId,
+    /// The sum of the modifiers, as shown in the population table.
+    pub happiness: f64,
+    /// The kind and current magnitude of every modifier, in order.
+    pub modifiers: Vec<(ModifierSpecId, f64)>,
+    pub paused: bool,
+    pub notes: String,
+}
+
+impl Simulation {
+    /// The same start as the app's, with the RNG seeded by `seed`.
+    ///
+    /// Every simulation allocates its own ids, so the same config and seed
+    /// always play out the same way:
+    ///
+    /// ```
+    /// use bug::{sim::Simulation, SimConfig};
+    ///
+    /// let play = |seed| {
+    ///     let mut simulation = Simulation::new(SimConfig::default(), seed);
+    ///     simulation.run(20);
+    ///     simulation.people()
+    /// };
+    /// assert_eq!(play(7), play(7));
+    /// ```
+    pub fn new(config: SimConfig, seed: u64) -> Self {
+        Self::from_state(GlobalState {
+            rng: SimRng::new(seed),
+            ..GlobalState::new_debug_instance(config)
+        })
+    }
+
+    fn from_state(state: GlobalState) -> Self {
+        let owner = Owner::new();
+        let state = owner.with(|| {
+            let state = Store::new(state);
+            GlobalState::check_invariants(state);
+            state
+        });
+        Self { owner, state }
+    }
+
+    /// The whole game as JSON, see [`GlobalState::to_json`].
+    pub fn state_json(&self) -> String {
+        self.state.read_untracked().to_json()
+    }
+
+    /// Replace the game with one saved by [`Simulation::state_json`].
+    /// The current config is kept unless `use_saved_config` is set, see [`GlobalState::from_save`].
+    /// If the text cannot be read, the current game is kept.
+    pub fn load_json(
+        &mut self,
+        json: &str,
+        use_saved_config: bool,
+    ) -> Result<(), serde_json::Error> {
+        let config = self.state.config().get_untracked();
+        *self = Self::from_state(GlobalState::from_save(json, config, use_saved_config)?);
+        Ok(())
+    }
+
+    /// Whether a goal failed. [`Simulation::tick`] must not be called then,
+    /// until [`Simulation::continue_in_sandbox`] is.
+    pub fn is_game_over(&self) -> bool {
+        self.state.read_untracked().is_game_over()
+    }
+
+    /// Keep playing after a goal failed, without evaluating goals anymore.
+    pub fn continue_in_sandbox(&mut self) {
+        self.owner
+            .with(|| GlobalState::continue_in_sandbox(self.state));
+    }
+
+    /// Finish one week, as the "Finish Week" button does.
+    ///
+    /// # Panics
+    ///
+    /// While [`Simulation::is_game_over`] holds, as weeks do not advance then.
+    /// Call [`Simulation::continue_in_sandbox`] to keep playing.
+    pub fn tick(&mut self) -> WeekOutcome {
+        assert!(
+            !self.is_game_over(),
+            "the game is over, call continue_in_sandbox to keep playing"
+        );
+        self.owner.with(|| {
+            // Nothing yields to the browser here, so each tick counts as its own flush.
+            reset_mutation_counts();
+            GlobalState::finish_week(self.state);
+            self.state
+                .last_week_outcome()
+                .get_untracked()
+                .expect("every finished week has an outcome")
+        })
+    }
+
+    /// Finish up to `weeks` weeks, stopping early if the game ends.
+    pub fn run(&mut self, weeks: u64) -> Vec<WeekOutcome> {
+        let mut outcomes = Vec::new();
+        for _ in 0..weeks {
+            if self.is_game_over() {
+                break;
+            }
+            outcomes.push(self.tick());
+        }
+        outcomes
+    }
+
+    pub fn person(&self, person_id: PersonId) -> Option<PersonSnapshot> {
+        self.people()
+            .into_iter()
+            .find(|person| person.id == person_id)
+    }
+
+    /// Everyone in every city, city by city in the order they were added.
+    pub fn people(&self) -> Vec<PersonSnapshot> {
+        let state = self.state.read_untracked();
+        state
+            .cities
+            .iter()
+            .flat_map(|city| {
+                city.population.people.iter().map(|person| {
+                    let happiness = &person.happiness;
+                    PersonSnapshot {
+                        id: person.id,
+                        city_id: city.id,
+                        happiness: happiness.current_total(),
+                        modifiers: happiness
+                            .happiness_modifiers
+                            .iter()
+                            .map(|modifier| (modifier.spec, modifier.current_magnitude()))
+                            .collect(),
+                        paused: person.paused,
+                        notes: person.notes.clone(),
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        self.owner.cleanup();
+    }
+}
@@ -0,0 +1,89 @@
+//! Class names rendered by the components, the hooks for the stylesheet in `index.html`.
+//!
+//! Names follow `block__element--modifier`. State classes are toggled with
+//! `{..toggle(NAME, move || condition)}`, never by formatting class strings.
+
+use leptos::tachys::html::class::{class, Class};
+
+/// The classes of one kind of [`EntityTable`](crate::EntityTable) row.
+#[derive(Debug, Clone, Copy)]
+pub struct RowClasses {
+    pub row: &'static str,
+    pub selected: &'static str,
+    pub hovered: &'static str,
+    pub pinned: &'static str,
+}
+
+impl RowClasses {
+    const fn new(
+        row: &'static str,
+        selected: &'static str,
+        hovered: &'static str,
+        pinned: &'static str,
+    ) -> Self {
+        Self {
+            row,
+            selected,
+            hovered,
+            pinned,
+        }
+    }
+}
+
+pub const ENTITY_ROW: RowClasses = RowClasses::new(
+    "entity-row",
+    "entity-row--selected",
+    "entity-row--hovered",
+    "entity-row--pinned",
+);
+pub const PERSON_ROW: RowClasses = RowClasses::new(
+    "person-row",
+    "person-row--selected",
+    "person-row--hovered",
+    "person-row--pinned",
+);
+pub const MODIFIER_ROW: RowClasses = RowClasses::new(
+    "modifier-row",
+    "modifier-row--selected",
+    "modifier-row--hovered",
+    "modifier-row--pinned",
+);
+
+pub const PERSON_LINK: &str = "person-link";
+pub const PERSON_LINK_HOVERED: &str = "person-link--hovered";
+
+pub const MODIFIER_ENTRY: &str = "modifier-entry";
+pub const MODIFIER_ENTRY_NEW: &str = "modifier-entry--new";
+pub const MODIFIER_ENTRY_DROP_TARGET: &str = "modifier-entry--drop-target";
+
+pub const DELTA: &str = "delta";
+pub const DELTA_POSITIVE: &str = "delta--positive";
+pub const DELTA_NEGATIVE: &str = "delta--negative";
+
+pub const SUMMARY: &str = "summary";
+pub const SUMMARY_VALUE: &str = "summary__value";
+
+pub const HAPPINESS_BADGE: &str = "happiness-badge";
+pub const HAPPINESS_BADGE_DOT: &str = "happiness-badge__dot";
+pub const HAPPINESS_BADGE_LABEL: &str = "happiness-badge__label";
+
+pub const TOASTS: &str = "toasts";
+pub const TOAST: &str = "toasts__toast";
+
+pub const CONTEXT_MENU: &str = "context-menu";
+
+pub const WEEK_SUMMARY: &str = "week-summary";
+pub const GOALS: &str = "goals";
+pub const GOAL: &str = "goals__goal";
+pub const ERROR: &str = "error";
+pub const VISUALLY_HIDDEN: &str = "visually-hidden";
+
+/// Adds `name` to the element's classes while `condition` is true.
+///
+/// Spread onto the element, as `view!` only allows one `class=` with a non-literal name.
+pub fn toggle<F>(name: &'static str, condition: F) -> Class<(&'static str, F)>
+where
+    F: Fn() -> bool + Send + 'static,
+{
+    class((name, condition))
+}
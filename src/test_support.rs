@@ -0,0 +1,213 @@
+//! Shared by the tests: rendering components to HTML and comparing it against fixtures.
+//!
+//! Components render natively through [`RenderHtml::to_html`], so snapshots need no browser.
+//! The fixtures are in `tests/fixtures`. Run the tests with `UPDATE_FIXTURES=1` to write the
+//! current output as the new fixtures, and review the change like any other.
+
+use std::{env, fs, path::PathBuf};
+
+use any_spawner::Executor;
+use leptos::prelude::*;
+use reactive_stores::Store;
+
+use crate::{
+    context::AppState, under_game_owner, CityId, GameGeneration, GlobalState, LiveAnnouncement,
+    PersonId, ReadOnly, UiPreferences,
+};
+
+/// Lets effects run, see [`Executor::poll_local`]. Rendering needs it, too.
+pub fn init_executor() {
+    // Every test thread shares one executor, so only the first call succeeds.
+    let _ = Executor::init_futures_executor();
+}
+
+/// Render `view` inside an [`AppState`] holding `state`, with the contexts the app provides
+/// around a game, and the city and person selected as at the start of a game.
+pub fn render_html<V: IntoView + 'static>(
+    state: GlobalState,
+    selection: (CityId, Option<PersonId>),
+    view: impl FnOnce() -> V + Send + 'static,
+) -> String {
+    init_executor();
+    let owner = Owner::new();
+    let html = owner.with(|| {
+        provide_context(Store::new(UiPreferences::default()));
+        provide_context(ReadOnly(state.read_only));
+        provide_context(GameGeneration::new());
+        provide_context(LiveAnnouncement(RwSignal::new(String::new())));
+        under_game_owner(selection, move || {
+            view! { <AppState state>{view()}</AppState> }
+        })
+        .to_html()
+    });
+    owner.cleanup();
+    html
+}
+
+/// Attributes whose values differ between renders of the same state.
+/// Hydration keys are counted per render.
+const VARYING_ATTRIBUTES: &[&str] = &["data-hk"];
+
+/// Elements without a closing tag.
+const VOID_ELEMENTS: &[&str] = &["area", "br", "col", "hr", "img", "input", "meta", "wbr"];
+
+/// Put every tag and every text on its own line, indented by nesting, so that fixtures diff
+/// line by line. Comments, like the markers between dynamic children, are left out,
+/// as are the [`VARYING_ATTRIBUTES`] and `ignored_attributes`. Text is trimmed.
+pub fn normalize_html(html: &str, ignored_attributes: &[&str]) -> String {
+    let mut lines = Vec::new();
+    let mut depth = 0usize;
+    let mut rest = html;
+    while !rest.is_empty() {
+        let text_end = rest.find('<').unwrap_or(rest.len());
+        let text = rest[..text_end].trim();
+        if !text.is_empty() {
+            lines.push(format!("{}{text}", "  ".repeat(depth)));
+        }
+        rest = &rest[text_end..];
+        if rest.is_empty() {
+            break;
+        }
+
+        let tag_end = tag_end(rest);
+        let tag = &rest[1..tag_end - 1];
+        rest = &rest[tag_end..];
+        if tag.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            depth = depth.saturating_sub(1);
+            lines.push(format!("{}</{}>", "  ".repeat(depth), name.trim()));
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        let mut normalized = format!("<{name}");
+        for (attribute, value) in attributes(&tag[name_end..]) {
+            if VARYING_ATTRIBUTES.contains(&attribute) || ignored_attributes.contains(&attribute) {
+                continue;
+            }
+            normalized.push(' ');
+            normalized.push_str(attribute);
+            if let Some(value) = value {
+                normalized.push_str(&format!("=\"{value}\""));
+            }
+        }
+        normalized.push('>');
+        lines.push(format!("{}{normalized}", "  ".repeat(depth)));
+        if !self_closing && !VOID_ELEMENTS.contains(&name) {
+            depth += 1;
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// The length of the tag at the start of `html`, up to and including its `>`.
+/// A `>` inside a quoted attribute value does not end the tag.
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (index, character) in html.char_indices() {
+        match (quote, character) {
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(open), _) if character == open => quote = None,
+            (None, '>') => return index + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// The attributes of a tag in order, with the values unquoted.
+fn attributes(mut text: &str) -> Vec<(&str, Option<&str>)> {
+    let mut attributes = Vec::new();
+    loop {
+        text = text.trim_start();
+        if text.is_empty() {
+            return attributes;
+        }
+        let name_end = text
+            .find(|character: char| character == '=' || character.is_whitespace())
+            .unwrap_or(text.len());
+        let name = &text[..name_end];
+        text = &text[name_end..];
+        let Some(value) = text.strip_prefix('=') else {
+            attributes.push((name, None));
+            continue;
+        };
+        let (value, rest) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value[1..];
+                let end = value.find(quote).unwrap_or(value.len());
+                (&value[..end], value.get(end + 1..).unwrap_or(""))
+            }
+            _ => value.split_at(value.find(char::is_whitespace).unwrap_or(value.len())),
+        };
+        attributes.push((name, Some(value)));
+        text = rest;
+    }
+}
+
+/// Compare `actual` against the fixture `name` in `tests/fixtures`, or write it there
+/// if `UPDATE_FIXTURES` is set to `1`. Panics with the differing lines on a mismatch.
+pub fn assert_matches_fixture(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    if env::var("UPDATE_FIXTURES").is_ok_and(|value| value == "1") {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|error| {
+        panic!(
+            "Cannot read the fixture {}: {error}\nRun with UPDATE_FIXTURES=1 to create it.",
+            path.display()
+        )
+    });
+    if expected != actual {
+        panic!(
+            "The output does not match the fixture {}:\n{}\
+             Run with UPDATE_FIXTURES=1 if the change is intended.",
+            path.display(),
+            line_diff(&expected, actual)
+        );
+    }
+}
+
+/// The lines between the common start and end of `expected` and `actual`, marked with `-`
+/// and `+`, with a few of the common lines around them.
+fn line_diff(expected: &str, actual: &str) -> String {
+    const CONTEXT: usize = 3;
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+    let common_start = expected
+        .iter()
+        .zip(&actual)
+        .take_while(|(expected, actual)| expected == actual)
+        .count();
+    let common_end = expected[common_start..]
+        .iter()
+        .rev()
+        .zip(actual[common_start..].iter().rev())
+        .take_while(|(expected, actual)| expected == actual)
+        .count();
+
+    let mut diff = format!("@@ line {} @@\n", common_start + 1);
+    for line in &expected[common_start.saturating_sub(CONTEXT)..common_start] {
+        diff.push_str(&format!("  {line}\n"));
+    }
+    for line in &expected[common_start..expected.len() - common_end] {
+        diff.push_str(&format!("- {line}\n"));
+    }
+    for line in &actual[common_start..actual.len() - common_end] {
+        diff.push_str(&format!("+ {line}\n"));
+    }
+    let after = expected.len() - common_end;
+    for line in &expected[after..(after + CONTEXT).min(expected.len())] {
+        diff.push_str(&format!("  {line}\n"));
+    }
+    diff
+}
@@ -0,0 +1,2295 @@
+//! Native tests of the simulation. They run the store functions under a plain [`Owner`],
+//! without rendering anything.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+use any_spawner::Executor;
+
+use super::{sim::Simulation, test_support::*, *};
+
+/// Draws of the tests, kept apart from the simulation's own labels.
+const TEST_LABEL: RngLabel = RngLabel("test");
+
+/// Run the effects scheduled on this thread.
+fn run_effects() {
+    Executor::poll_local();
+}
+
+/// Run `f` with a fresh debug instance, disposing it afterwards.
+fn with_state<T>(config: SimConfig, f: impl FnOnce(Store<GlobalState>) -> T) -> T {
+    let owner = Owner::new();
+    let result = owner.with(|| f(Store::new(GlobalState::new_debug_instance(config))));
+    owner.cleanup();
+    result
+}
+
+fn population(state: Store<GlobalState>, city_id: CityId) -> Field<Population> {
+    GlobalState::city(state, city_id)
+        .expect("the debug instance has this city")
+        .population()
+        .into()
+}
+
+/// The ids of the people living in every city, city by city.
+fn everyone(state: Store<GlobalState>) -> Vec<PersonId> {
+    state
+        .read_untracked()
+        .cities
+        .iter()
+        .flat_map(|city| city.population.people.iter().map(|person| person.id))
+        .collect()
+}
+
+fn first_person(state: Store<GlobalState>) -> Field<Person> {
+    let (_, person) = Population::iter_people(population(state, CityId(1)))
+        .next()
+        .expect("the debug instance has people");
+    person
+}
+
+#[test]
+fn finish_week_records_modifier_history() {
+    with_state(SimConfig::default(), |state| {
+        let config = SimConfig::default();
+        let person = first_person(state);
+        let before =
+            ModifierHistory::snapshot(&person.happiness().happiness_modifiers().read_untracked());
+
+        let mut ids = state.ids().get_untracked();
+        Happiness::finish_week(person, 1, &config, &mut ModifierStats::new(), &mut ids);
+
+        let after =
+            ModifierHistory::snapshot(&person.happiness().happiness_modifiers().read_untracked());
+        let history = person.happiness().history().read_untracked();
+        let weeks: Vec<_> = history.snapshots.iter().map(|(week, _)| *week).collect();
+        assert_eq!(weeks, [0, 1]);
+        assert_eq!(history.at(0), Some(&*before));
+        assert_eq!(history.at(1), Some(&*after));
+        // Later weeks without a snapshot show the latest one.
+        assert_eq!(history.at(5), Some(&*after));
+    });
+}
+
+/// One game as started by [`GameRoot`], with what the test registered under its owner.
+struct StartedGame {
+    state: Store<GlobalState>,
+    effect_runs: Arc<AtomicUsize>,
+    cleaned_up: Arc<AtomicUsize>,
+}
+
+#[test]
+fn new_games_dispose_the_previous_game() {
+    init_executor();
+    let root = Owner::new();
+    root.with(|| {
+        // Outlives every game, like the signals of the page around them.
+        let outer = RwSignal::new(0);
+        let mut games = Vec::new();
+        let mut view = None;
+        for _ in 0..3 {
+            // Re-rendering GameRoot drops the view of the previous game.
+            drop(view.take());
+            let (global_state, selection) = new_game(SimConfig::default(), ReadOnly(false));
+            let (city_id, person_id) = selection;
+            assert_eq!(global_state.city_of(person_id.unwrap()), Some(city_id));
+            view = Some(under_game_owner(selection, || {
+                let state = Store::new(global_state);
+                // A fast-forward that is still running when the game ends.
+                state.fast_forward().set(Some(FastForward {
+                    progress: Progress {
+                        done: 0,
+                        total: 100,
+                    },
+                    cancel_requested: false,
+                }));
+                let effect_runs = Arc::new(AtomicUsize::new(0));
+                Effect::new({
+                    let effect_runs = effect_runs.clone();
+                    move || {
+                        outer.track();
+                        effect_runs.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+                // Stands in for the interval cleanup of the auto-advance loop.
+                let cleaned_up = Arc::new(AtomicUsize::new(0));
+                on_cleanup({
+                    let cleaned_up = cleaned_up.clone();
+                    move || {
+                        cleaned_up.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+                games.push(StartedGame {
+                    state,
+                    effect_runs,
+                    cleaned_up,
+                });
+            }));
+            run_effects();
+        }
+
+        let runs_before: Vec<_> = games
+            .iter()
+            .map(|game| game.effect_runs.load(Ordering::Relaxed))
+            .collect();
+        #[cfg(any(debug_assertions, feature = "metrics"))]
+        record_mutations();
+        outer.set(1);
+        run_effects();
+
+        // Two new games later, the effects of the first game still do not run.
+        let (current, disposed) = games.split_last().unwrap();
+        for (game, runs_before) in disposed.iter().zip(&runs_before) {
+            assert_eq!(game.effect_runs.load(Ordering::Relaxed), *runs_before);
+            assert_eq!(game.cleaned_up.load(Ordering::Relaxed), 1);
+            assert!(game.state.try_read_untracked().is_none());
+            // The next chunk of the orphaned fast-forward finds the store gone and stops.
+            GlobalState::run_fast_forward_chunk(game.state);
+        }
+        assert_eq!(
+            current.effect_runs.load(Ordering::Relaxed),
+            runs_before[2] + 1
+        );
+        assert_eq!(current.cleaned_up.load(Ordering::Relaxed), 0);
+        assert_eq!(current.state.week().get_untracked(), 0);
+        assert!(current.state.fast_forward().read_untracked().is_some());
+        #[cfg(any(debug_assertions, feature = "metrics"))]
+        {
+            // Nothing wrote to the stores of the old games.
+            assert!(recorded_mutations().is_empty());
+            // A chunk of the current game's fast-forward is recorded, so the check can fail.
+            assert!(GlobalState::fast_forward_step(current.state));
+            assert!(!recorded_mutations().is_empty());
+        }
+    });
+    root.cleanup();
+}
+
+#[test]
+fn person_fields_follow_their_person_through_removals_and_migrations() {
+    with_state(SimConfig::default(), |state| {
+        let cities = [CityId(1), CityId(2)];
+        // Every person's field in every city, taken before anything moves.
+        let mut held = Vec::new();
+        for city_id in cities {
+            for (person_id, person) in Population::iter_people(population(state, city_id)) {
+                held.push((city_id, person_id, person));
+            }
+        }
+        let ids: Vec<_> = held.iter().map(|&(_, person_id, _)| person_id).collect();
+        let mut rng = SimRng::new(3);
+
+        for _ in 0..300 {
+            let person_id = ids[rng.next_u64(TEST_LABEL) as usize % ids.len()];
+            let trashed_in = cities.into_iter().find(|&city_id| {
+                population(state, city_id)
+                    .trash()
+                    .read_untracked()
+                    .iter()
+                    .any(|(person, _)| person.id == person_id)
+            });
+            if let Some(city_id) = trashed_in {
+                GlobalState::restore_person(state, city_id, person_id).unwrap();
+            } else if rng.next_f64(TEST_LABEL) < 0.3 {
+                GlobalState::trash_person(state, person_id).unwrap();
+            } else {
+                let from = state.read_untracked().city_of(person_id).unwrap();
+                let to = cities.into_iter().find(|&city_id| city_id != from).unwrap();
+                GlobalState::migrate_person(state, person_id, from, to).unwrap();
+            }
+
+            for (city_id, person_id, person) in &held {
+                let lives_here = state.read_untracked().city_of(*person_id) == Some(*city_id);
+                match person.try_read_untracked() {
+                    Some(person) => {
+                        assert!(lives_here);
+                        assert_eq!(person.id, *person_id);
+                    }
+                    None => assert!(!lives_here),
+                }
+            }
+            assert_eq!(state.read_untracked().validate(), []);
+        }
+    });
+}
+
+#[test]
+fn modifier_tables_follow_the_selected_person() {
+    let render = |switch: bool, shown: usize| {
+        let html = render_html(
+            GlobalState::new_debug_instance(SimConfig::default()),
+            (CityId(1), None),
+            move || {
+                let state = use_global_state();
+                let people: Vec<_> = Population::iter_people(population(state, CityId(1)))
+                    .map(|(person_id, _)| person_id)
+                    .collect();
+                // Tells the second person's table apart from the first one's.
+                GlobalState::add_pinned_modifier(state, people[1], ModifierSpecId::DEFAULT, None)
+                    .unwrap();
+                let person_id = RwSignal::new(people[if switch { 0 } else { shown }]);
+                let table = view! { <HappinessModifierTable city_id=CityId(1) person_id /> };
+                person_id.set(people[shown]);
+                table
+            },
+        );
+        normalize_html(&html, &[])
+    };
+
+    let second = render(false, 1);
+    assert_ne!(render(false, 0), second);
+    assert_eq!(render(true, 1), second);
+}
+
+#[test]
+fn mounted_modifier_tables_look_up_their_own_person_through_rapid_changes() {
+    const STEPS: usize = 300;
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let expected_rows = Arc::new(AtomicUsize::new(0));
+    let html = render_html(
+        GlobalState::new_debug_instance(SimConfig::default()),
+        (CityId(1), None),
+        {
+            let lookups = lookups.clone();
+            let expected_rows = expected_rows.clone();
+            move || {
+                let state = use_global_state();
+                let cities = [CityId(1), CityId(2)];
+                let watched: Vec<_> = Population::iter_people(population(state, CityId(1)))
+                    .map(|(person_id, _)| person_id)
+                    .collect();
+                let tables = watched
+                    .iter()
+                    .map(|&person_id| {
+                        // The field the table holds, and a fresh lookup like the table's,
+                        // both read again after every change.
+                        let held =
+                            Population::person(population(state, CityId(1)), person_id).unwrap();
+                        let lookup = Signal::derive(move || {
+                            if let Some(person) = held.try_read() {
+                                assert_eq!(person.id, person_id);
+                            }
+                            let person =
+                                Population::person(population(state, CityId(1)), person_id);
+                            match person {
+                                Ok(person) => assert_eq!(person.id().get(), person_id),
+                                Err(error) => {
+                                    assert_eq!(error, SimError::PersonNotFound(person_id));
+                                    assert_ne!(
+                                        state.read_untracked().city_of(person_id),
+                                        Some(CityId(1))
+                                    );
+                                }
+                            }
+                        });
+                        Effect::new({
+                            let lookups = lookups.clone();
+                            move || {
+                                lookup.get();
+                                lookups.fetch_add(1, Ordering::Relaxed);
+                            }
+                        });
+                        view! { <HappinessModifierTable city_id=CityId(1) person_id /> }
+                    })
+                    .collect_view();
+                run_effects();
+
+                let everyone = everyone(state);
+                let mut rng = SimRng::new(7);
+                for _ in 0..STEPS {
+                    reset_mutation_counts();
+                    let person_id = everyone[rng.next_u64(TEST_LABEL) as usize % everyone.len()];
+                    let trashed_in = cities.into_iter().find(|&city_id| {
+                        population(state, city_id)
+                            .trash()
+                            .read_untracked()
+                            .iter()
+                            .any(|(person, _)| person.id == person_id)
+                    });
+                    if let Some(city_id) = trashed_in {
+                        GlobalState::restore_person(state, city_id, person_id).unwrap();
+                        run_effects();
+                        continue;
+                    }
+                    match rng.next_u64(TEST_LABEL) % 4 {
+                        0 => GlobalState::trash_person(state, person_id).unwrap(),
+                        1 => {
+                            let from = state.read_untracked().city_of(person_id).unwrap();
+                            let to = cities.into_iter().find(|&city_id| city_id != from).unwrap();
+                            GlobalState::migrate_person(state, person_id, from, to).unwrap();
+                        }
+                        2 => {
+                            GlobalState::add_pinned_modifier(
+                                state,
+                                person_id,
+                                ModifierSpecId::NOVELTY,
+                                None,
+                            )
+                            .unwrap();
+                        }
+                        _ => {
+                            let person = GlobalState::find_person(state, person_id).unwrap();
+                            let ids = modifier_ids(&person.read_untracked());
+                            if let Some(&modifier_id) = ids.first() {
+                                GlobalState::remove_modifier(state, person_id, modifier_id)
+                                    .unwrap();
+                            }
+                        }
+                    }
+                    run_effects();
+                }
+
+                // Bring the watched people home, so that every table renders its person.
+                for &person_id in &watched {
+                    for city_id in cities {
+                        let _ = GlobalState::restore_person(state, city_id, person_id);
+                    }
+                    let city_id = state.read_untracked().city_of(person_id).unwrap();
+                    if city_id != CityId(1) {
+                        GlobalState::migrate_person(state, person_id, city_id, CityId(1)).unwrap();
+                    }
+                    let person = GlobalState::find_person(state, person_id).unwrap();
+                    expected_rows.fetch_add(
+                        person
+                            .happiness()
+                            .happiness_modifiers()
+                            .read_untracked()
+                            .len(),
+                        Ordering::Relaxed,
+                    );
+                }
+                run_effects();
+                tables
+            }
+        },
+    );
+    // Every lookup ran once when mounted and again after most of the changes.
+    assert!(lookups.load(Ordering::Relaxed) > STEPS, "{lookups:?}");
+    assert_eq!(
+        html.matches("<tr class=\"modifier-row").count(),
+        expected_rows.load(Ordering::Relaxed)
+    );
+}
+
+#[test]
+fn apply_modifier_to_matching_adds_one_modifier_to_each_match() {
+    with_state(SimConfig::default(), |state| {
+        let modifier_counts = || -> Vec<_> {
+            population(state, CityId(1))
+                .people()
+                .read_untracked()
+                .iter()
+                .map(|person| (person.id, person.happiness.happiness_modifiers.len()))
+                .collect()
+        };
+        let before = modifier_counts();
+        let template = ModifierTemplate {
+            spec: ModifierSpecId::DEFAULT,
+            magnitude: Some(0.2),
+            pinned: true,
+        };
+
+        let count = GlobalState::apply_modifier_to_matching(
+            state,
+            CityId(1),
+            |person| person.id.0 % 2 == 1,
+            template,
+        )
+        .unwrap();
+
+        let after = modifier_counts();
+        assert_eq!(count, before.iter().filter(|(id, _)| id.0 % 2 == 1).count());
+        for ((person_id, before), (_, after)) in before.iter().zip(&after) {
+            let added = if person_id.0 % 2 == 1 { 1 } else { 0 };
+            assert_eq!(*after, before + added);
+        }
+        assert_eq!(state.read_untracked().validate(), []);
+    });
+}
+
+#[test]
+fn changing_a_person_re_renders_only_their_row() {
+    init_executor();
+    with_state(SimConfig::default(), |state| {
+        // Fixed rows, so that only the table itself can track the people.
+        let rows = Signal::stored(
+            Population::iter_people(population(state, CityId(1)))
+                .map(|(_, person)| person)
+                .collect::<Vec<_>>(),
+        );
+        let columns = Arc::new(vec![EntityColumn::new("Notes", |person: Field<Person>| {
+            person.notes().get()
+        })
+        .sortable(|person| person.id().get().0 as f64)]);
+
+        // Mirrors the `For` of the table: the order is recomputed on its own, and each row is
+        // rendered once, then only updated through its memoized cells.
+        let order_runs = Arc::new(AtomicUsize::new(0));
+        let row_renders: Arc<Mutex<HashMap<PersonId, usize>>> = Default::default();
+        let sorted_rows = entity_rows_in_order(rows, columns, || None, None);
+        for (key, row) in sorted_rows() {
+            let notes = Memo::new(move |_| row.notes().get());
+            let row_renders = row_renders.clone();
+            Effect::new(move || {
+                let _ = notes.read();
+                *row_renders.lock().unwrap().entry(key).or_default() += 1;
+            });
+        }
+        Effect::new({
+            let order_runs = order_runs.clone();
+            move || {
+                sorted_rows();
+                order_runs.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        run_effects();
+        let before = row_renders.lock().unwrap().clone();
+        assert!(before.len() > 1);
+        assert!(before.values().all(|renders| *renders == 1));
+
+        let (changed, person) = Population::iter_people(population(state, CityId(1)))
+            .next()
+            .unwrap();
+        person.notes().set("changed".to_string());
+        run_effects();
+
+        assert_eq!(order_runs.load(Ordering::Relaxed), 1);
+        let after = row_renders.lock().unwrap().clone();
+        for (key, renders) in after {
+            assert_eq!(renders, if key == changed { 2 } else { 1 }, "row {key}");
+        }
+    });
+}
+
+#[test]
+fn missing_cities_are_city_not_found() {
+    with_state(SimConfig::default(), |state| {
+        let missing = CityId(99);
+        assert_eq!(
+            GlobalState::city(state, missing).err(),
+            Some(SimError::CityNotFound(missing))
+        );
+        let (person_id, _) = Population::iter_people(population(state, CityId(1)))
+            .next()
+            .unwrap();
+        assert_eq!(
+            GlobalState::migrate_person(state, person_id, CityId(1), missing),
+            Err(SimError::CityNotFound(missing))
+        );
+        // A failed migration leaves the person where they were.
+        assert_eq!(state.read_untracked().city_of(person_id), Some(CityId(1)));
+        assert_eq!(
+            GlobalState::restore_person(state, missing, person_id),
+            Err(SimError::CityNotFound(missing))
+        );
+    });
+}
+
+#[test]
+fn missing_people_are_person_not_found() {
+    with_state(SimConfig::default(), |state| {
+        let missing = PersonId(999);
+        assert_eq!(
+            Population::person(population(state, CityId(1)), missing).err(),
+            Some(SimError::PersonNotFound(missing))
+        );
+        assert_eq!(
+            GlobalState::trash_person(state, missing),
+            Err(SimError::PersonNotFound(missing))
+        );
+    });
+}
+
+#[test]
+fn missing_modifiers_are_modifier_not_found() {
+    with_state(SimConfig::default(), |state| {
+        let person_id = first_person(state).id().get_untracked();
+        let mut ids = state.ids().get_untracked();
+        let missing = HappinessModifier::create(ModifierSpecId::NOVELTY, 0, &mut ids).id;
+        assert_eq!(
+            GlobalState::set_modifier_pinned(state, person_id, missing, true),
+            Err(SimError::ModifierNotFound(missing))
+        );
+        assert_eq!(
+            GlobalState::set_modifier_magnitude(state, person_id, missing, 0.5),
+            Err(MagnitudeError::Sim(SimError::ModifierNotFound(missing)))
+        );
+        let nobody = PersonId(999);
+        assert_eq!(
+            GlobalState::set_modifier_pinned(state, nobody, missing, true),
+            Err(SimError::PersonNotFound(nobody))
+        );
+    });
+}
+
+#[test]
+fn an_index_pointing_at_another_person_is_index_corrupt() {
+    with_state(SimConfig::default(), |state| {
+        let population = population(state, CityId(1));
+        let (person_id, _) = Population::iter_people(population).next().unwrap();
+        let last = population.people().read_untracked().len() - 1;
+        population.write().people_by_id.insert(person_id, last);
+        assert_eq!(
+            Population::person(population, person_id).err(),
+            Some(SimError::IndexCorrupt(person_id))
+        );
+    });
+}
+
+#[test]
+fn migrating_from_the_wrong_city_is_not_in_city() {
+    with_state(SimConfig::default(), |state| {
+        let (person_id, _) = Population::iter_people(population(state, CityId(1)))
+            .next()
+            .unwrap();
+        assert_eq!(
+            GlobalState::migrate_person(state, person_id, CityId(2), CityId(1)),
+            Err(SimError::NotInCity {
+                person_id,
+                city_id: CityId(2),
+            })
+        );
+    });
+}
+
+#[test]
+fn restoring_someone_not_removed_is_not_in_trash() {
+    with_state(SimConfig::default(), |state| {
+        let (person_id, _) = Population::iter_people(population(state, CityId(1)))
+            .next()
+            .unwrap();
+        assert_eq!(
+            GlobalState::restore_person(state, CityId(1), person_id),
+            Err(SimError::NotInTrash {
+                person_id,
+                city_id: CityId(1),
+            })
+        );
+    });
+}
+
+#[test]
+fn migrating_to_the_same_city_is_same_city() {
+    with_state(SimConfig::default(), |state| {
+        let (person_id, _) = Population::iter_people(population(state, CityId(1)))
+            .next()
+            .unwrap();
+        assert_eq!(
+            GlobalState::migrate_person(state, person_id, CityId(1), CityId(1)),
+            Err(SimError::SameCity(CityId(1)))
+        );
+    });
+}
+
+#[test]
+fn changes_in_read_only_mode_are_read_only() {
+    with_state(SimConfig::default(), |state| {
+        state.read_only().set(true);
+        let (person_id, _) = Population::iter_people(population(state, CityId(1)))
+            .next()
+            .unwrap();
+        assert_eq!(
+            GlobalState::trash_person(state, person_id),
+            Err(SimError::ReadOnly)
+        );
+        let template = ModifierTemplate {
+            spec: ModifierSpecId::DEFAULT,
+            magnitude: None,
+            pinned: true,
+        };
+        assert_eq!(
+            GlobalState::apply_modifier_to_matching(state, CityId(1), |_| true, template),
+            Err(SimError::ReadOnly)
+        );
+        assert_eq!(state.read_untracked().city_of(person_id), Some(CityId(1)));
+    });
+}
+
+// Writes during a read are only refused in debug builds.
+#[cfg(debug_assertions)]
+#[test]
+fn changes_while_rendering_are_write_during_read() {
+    with_state(SimConfig::default(), |state| {
+        let (person_id, _) = Population::iter_people(population(state, CityId(1)))
+            .next()
+            .unwrap();
+        let result = {
+            let _scope = ReadScope::enter();
+            GlobalState::trash_person(state, person_id)
+        };
+        assert_eq!(result, Err(SimError::WriteDuringRead));
+        assert_eq!(state.read_untracked().city_of(person_id), Some(CityId(1)));
+    });
+}
+
+#[test]
+fn reading_a_modifier_while_the_store_is_written_is_store_read_unavailable() {
+    with_state(SimConfig::default(), |state| {
+        let modifier: Field<HappinessModifier> = first_person(state)
+            .happiness()
+            .happiness_modifiers()
+            .iter_unkeyed()
+            .next()
+            .expect("the debug instance gives everyone a modifier")
+            .into();
+        assert!(HappinessModifier::happiness(modifier).is_ok());
+
+        let week = state.week();
+        let guard = week.write();
+        assert_eq!(
+            HappinessModifier::happiness(modifier),
+            Err(SimError::StoreReadUnavailable)
+        );
+        drop(guard);
+        assert!(HappinessModifier::happiness(modifier).is_ok());
+    });
+}
+
+/// The violations of a debug instance after `corrupt` changed it.
+fn violations_after(corrupt: impl FnOnce(&mut GlobalState)) -> Vec<InvariantViolation> {
+    let mut state = GlobalState::new_debug_instance(SimConfig::default());
+    assert_eq!(state.validate(), []);
+    corrupt(&mut state);
+    state.validate()
+}
+
+#[test]
+fn validate_reports_index_entries_out_of_bounds() {
+    let violations = violations_after(|state| {
+        let population = &mut state.cities[0].population;
+        let person_id = population.people[0].id;
+        population.people_by_id.insert(person_id, 99);
+    });
+    let person_id = PersonId(1);
+    assert_eq!(
+        violations,
+        [
+            InvariantViolation::IndexOutOfBounds {
+                person_id,
+                index: 99
+            },
+            InvariantViolation::PersonNotIndexed {
+                person_id,
+                index: 0
+            },
+        ]
+    );
+}
+
+#[test]
+fn validate_reports_index_entries_pointing_at_another_person() {
+    let violations = violations_after(|state| state.cities[0].population.people.swap(0, 1));
+    let (first, second) = (PersonId(1), PersonId(2));
+    assert_eq!(
+        violations,
+        [
+            InvariantViolation::IndexPointsToWrongPerson {
+                person_id: first,
+                index: 0,
+                actual_person_id: second,
+            },
+            InvariantViolation::IndexPointsToWrongPerson {
+                person_id: second,
+                index: 1,
+                actual_person_id: first,
+            },
+            InvariantViolation::PersonNotIndexed {
+                person_id: second,
+                index: 0,
+            },
+            InvariantViolation::PersonNotIndexed {
+                person_id: first,
+                index: 1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn validate_reports_duplicate_ids() {
+    let violations = violations_after(|state| {
+        state.cities[1].id = state.cities[0].id;
+        let population = &mut state.cities[0].population;
+        let copy = population.people[0].clone();
+        population.trash.push((copy, 0));
+    });
+    assert!(violations.contains(&InvariantViolation::DuplicatePersonId(PersonId(1))));
+    assert!(violations.contains(&InvariantViolation::DuplicateCityId(CityId(1))));
+}
+
+#[test]
+fn validate_reports_people_living_in_several_cities() {
+    let violations = violations_after(|state| {
+        let person = state.cities[0].population.people[0].clone();
+        let population = &mut state.cities[1].population;
+        population.people.push(person);
+        population.rebuild_index();
+    });
+    // Modifier ids are only compared within a city, so the copied modifiers are not reported.
+    assert_eq!(
+        violations,
+        [InvariantViolation::PersonInSeveralCities {
+            person_id: PersonId(1),
+            cities: (CityId(1), CityId(2)),
+        }]
+    );
+}
+
+#[test]
+fn validate_reports_duplicate_modifier_ids_and_unknown_specs() {
+    let mut first_modifier = None;
+    let violations = violations_after(|state| {
+        let modifiers = &mut state.cities[0].population.people[0]
+            .happiness
+            .happiness_modifiers;
+        let mut copy = modifiers[0].clone();
+        copy.spec = ModifierSpecId(9999);
+        first_modifier = Some(copy.id);
+        modifiers.push(copy);
+    });
+    let modifier_id = first_modifier.unwrap();
+    assert_eq!(
+        violations,
+        [
+            InvariantViolation::DuplicateHappinessModifierId(modifier_id),
+            InvariantViolation::UnknownModifierSpec {
+                modifier_id,
+                spec_id: ModifierSpecId(9999),
+            },
+        ]
+    );
+}
+
+#[test]
+fn validate_reports_id_allocators_behind_existing_ids() {
+    let violations = violations_after(|state| {
+        state.ids.next_person_id = 2;
+        state.ids.next_modifier_id -= 1;
+    });
+    let next_modifier_id = GlobalState::new_debug_instance(SimConfig::default())
+        .ids
+        .next_modifier_id
+        - 1;
+    assert!(
+        violations.contains(&InvariantViolation::PersonIdAllocatorBehind {
+            next: 2,
+            existing: PersonId(2),
+        })
+    );
+    assert!(
+        !violations.contains(&InvariantViolation::PersonIdAllocatorBehind {
+            next: 2,
+            existing: PersonId(1),
+        })
+    );
+    assert!(
+        violations.contains(&InvariantViolation::HappinessModifierIdAllocatorBehind {
+            next: next_modifier_id,
+            existing: HappinessModifierId(next_modifier_id),
+        })
+    );
+    assert!(violations.iter().all(|violation| matches!(
+        violation,
+        InvariantViolation::PersonIdAllocatorBehind { .. }
+            | InvariantViolation::HappinessModifierIdAllocatorBehind { .. }
+    )));
+}
+
+#[test]
+fn weeks_finish_without_anyone_left() {
+    with_state(SimConfig::default(), |state| {
+        for person_id in everyone(state) {
+            GlobalState::trash_person(state, person_id).unwrap();
+        }
+        // A failed goal would stop the weeks before they could break anything.
+        GlobalState::continue_in_sandbox(state);
+
+        for week in 1..=20 {
+            reset_mutation_counts();
+            GlobalState::finish_week(state);
+            assert_eq!(state.week().get_untracked(), week);
+            assert_eq!(GlobalState::overall_happiness(state), (0, None));
+            for city_id in [CityId(1), CityId(2)] {
+                assert_eq!(
+                    Population::average_happiness(population(state, city_id)),
+                    None
+                );
+            }
+        }
+        assert_eq!(state.read_untracked().validate(), []);
+        assert!(state.invariant_violations().read_untracked().is_empty());
+    });
+}
+
+#[test]
+fn decaying_modifiers_fade_until_removed_and_others_stay_constant() {
+    with_state(SimConfig::default(), |state| {
+        let config = SimConfig::default();
+        let person = first_person(state);
+        let mut ids = state.ids().get_untracked();
+        let decaying = HappinessModifier::create(ModifierSpecId::NOVELTY, 0, &mut ids);
+        let mut constant = HappinessModifier::create(ModifierSpecId::DEFAULT, 0, &mut ids);
+        constant.remaining_weeks = None;
+        let (decaying_id, constant_id) = (decaying.id, constant.id);
+        person
+            .happiness()
+            .happiness_modifiers()
+            .write()
+            .extend([decaying, constant]);
+        let magnitude_of = |modifier_id| {
+            person
+                .happiness()
+                .happiness_modifiers()
+                .read_untracked()
+                .iter()
+                .find(|modifier| modifier.id == modifier_id)
+                .map(HappinessModifier::current_magnitude)
+        };
+
+        let mut decaying_magnitudes = vec![magnitude_of(decaying_id).unwrap()];
+        for week in 1..=100 {
+            Happiness::finish_week(person, week, &config, &mut ModifierStats::new(), &mut ids);
+            if let Some(magnitude) = magnitude_of(decaying_id) {
+                decaying_magnitudes.push(magnitude);
+            }
+            assert_eq!(magnitude_of(constant_id), Some(0.5), "week {week}");
+        }
+
+        // 0.3 shrinks by a tenth every week, and drops below 0.01 in week 33.
+        assert_eq!(decaying_magnitudes.len(), 33);
+        for pair in decaying_magnitudes.windows(2) {
+            assert!((pair[1] - pair[0] * 0.9).abs() < 1e-12, "{pair:?}");
+        }
+        assert!(decaying_magnitudes
+            .iter()
+            .all(|magnitude| *magnitude >= 0.01));
+    });
+}
+
+#[test]
+fn migrations_neither_duplicate_nor_lose_anyone() {
+    let config = SimConfig {
+        migration_happiness_gap: 0.0,
+        migration_probability: 0.3,
+        ..SimConfig::default()
+    };
+    let mut simulation = Simulation::new(config, 3);
+    simulation.continue_in_sandbox();
+    let ids = |simulation: &Simulation| {
+        let mut ids: Vec<_> = simulation.people().iter().map(|person| person.id).collect();
+        ids.sort();
+        ids
+    };
+    let everyone = ids(&simulation);
+
+    let mut migrations = 0;
+    for _ in 0..200 {
+        let outcome = simulation.tick();
+        migrations += outcome.migrations.len();
+        // Sorted, so a duplicate would show up as an extra id.
+        assert_eq!(ids(&simulation), everyone, "week {}", outcome.week);
+        for migration in &outcome.migrations {
+            let person = simulation.person(migration.person_id).unwrap();
+            assert_eq!(person.city_id, migration.to);
+        }
+    }
+    assert!(migrations > 0);
+}
+
+/// Run one tick system on its own, as if it were the only one finishing `week`.
+fn run_system(
+    state: Store<GlobalState>,
+    system: &dyn TickSystem,
+    week: u64,
+    config: &SimConfig,
+) -> WeekOutcome {
+    let mut rng = state.rng().get_untracked();
+    let mut ids = state.ids().get_untracked();
+    let mut outcome = WeekOutcome::new(week);
+    system.run(&mut TickContext {
+        state,
+        config,
+        rng: &mut rng,
+        ids: &mut ids,
+        week,
+        outcome: &mut outcome,
+    });
+    state.rng().set(rng);
+    state.ids().set(ids);
+    outcome
+}
+
+#[test]
+fn modifier_system_gives_everyone_the_weekly_modifier() {
+    with_state(SimConfig::default(), |state| {
+        let people = everyone(state);
+        let before: Vec<_> = people
+            .iter()
+            .map(|&person_id| {
+                modifier_ids(
+                    &GlobalState::find_person(state, person_id)
+                        .unwrap()
+                        .read_untracked(),
+                )
+            })
+            .collect();
+        let outcome = run_system(state, &ModifierSystem, 1, &SimConfig::default());
+
+        let stats = outcome.modifier_stats[&ModifierSpecId::DEFAULT];
+        assert_eq!(stats.added as usize, people.len());
+        for (person_id, before) in people.into_iter().zip(before) {
+            let person = GlobalState::find_person(state, person_id).unwrap();
+            let modifiers = person.happiness().happiness_modifiers().read_untracked();
+            // A new modifier, which keeps the week of the initial one it replaces.
+            assert!(modifiers
+                .iter()
+                .any(|modifier| modifier.spec == ModifierSpecId::DEFAULT
+                    && !before.contains(&modifier.id)
+                    && modifier.created_week == 0));
+        }
+        assert!(outcome.migrations.is_empty());
+        assert!(outcome.purged_people.is_empty());
+        assert_eq!(state.read_untracked().validate(), []);
+    });
+}
+
+#[test]
+fn only_modifiers_added_this_week_are_highlighted_as_new() {
+    let state = GlobalState::new_debug_instance(SimConfig::default());
+    let html = render_html(state, (CityId(1), None), || {
+        let state = use_global_state();
+        for _ in 0..2 {
+            reset_mutation_counts();
+            GlobalState::finish_week(state);
+        }
+        let person_id = first_person(state).id().get_untracked();
+        GlobalState::add_pinned_modifier(state, person_id, ModifierSpecId::NOVELTY, None).unwrap();
+        view! { <HappinessModifierTable city_id=CityId(1) person_id /> }
+    });
+    // The weekly modifier is recomputed every week, but only the pinned one is new.
+    assert_eq!(
+        html.matches(styles::MODIFIER_ENTRY_NEW).count(),
+        1,
+        "{html}"
+    );
+}
+
+#[test]
+fn migration_system_moves_people_to_the_happiest_city() {
+    let config = SimConfig {
+        migration_happiness_gap: 0.1,
+        migration_probability: 1.0,
+        ..SimConfig::default()
+    };
+    with_state(config.clone(), |state| {
+        let southerners = Population::iter_people(population(state, CityId(2)))
+            .map(|(person_id, _)| person_id)
+            .collect::<Vec<_>>();
+        for &person_id in &southerners {
+            GlobalState::add_pinned_modifier(state, person_id, ModifierSpecId::NOVELTY, Some(1.0));
+        }
+        let northerners = Population::iter_people(population(state, CityId(1)))
+            .map(|(person_id, _)| person_id)
+            .collect::<Vec<_>>();
+
+        let outcome = run_system(state, &MigrationSystem, 1, &config);
+
+        let migrants: Vec<_> = outcome
+            .migrations
+            .iter()
+            .map(|migration| {
+                assert_eq!((migration.from, migration.to), (CityId(1), CityId(2)));
+                migration.person_id
+            })
+            .collect();
+        assert_eq!(migrants, northerners);
+        let state = state.read_untracked();
+        assert!(state.cities[0].population.people.is_empty());
+        assert_eq!(
+            state.cities[1].population.people.len(),
+            southerners.len() + northerners.len()
+        );
+        assert_eq!(state.validate(), []);
+    });
+}
+
+#[test]
+fn trash_system_purges_people_after_the_retention_period() {
+    with_state(SimConfig::default(), |state| {
+        let config = SimConfig::default();
+        let person_id = first_person(state).id().get_untracked();
+        GlobalState::trash_person(state, person_id).unwrap();
+
+        let retention_weeks = config.trash_retention_weeks;
+        let kept = run_system(state, &TrashSystem, retention_weeks, &config);
+        assert!(kept.purged_people.is_empty());
+        assert_eq!(
+            population(state, CityId(1)).trash().read_untracked().len(),
+            1
+        );
+
+        let purged = run_system(state, &TrashSystem, retention_weeks + 1, &config);
+        assert_eq!(purged.purged_people, [person_id]);
+        assert!(population(state, CityId(1))
+            .trash()
+            .read_untracked()
+            .is_empty());
+        assert!(purged.migrations.is_empty());
+    });
+}
+
+/// The ids of the modifiers of a person, to tell whether they came back unchanged.
+fn modifier_ids(person: &Person) -> Vec<HappinessModifierId> {
+    person
+        .happiness
+        .happiness_modifiers
+        .iter()
+        .map(|modifier| modifier.id)
+        .collect()
+}
+
+#[test]
+fn restored_people_keep_their_id_and_modifiers() {
+    with_state(SimConfig::default(), |state| {
+        // The person a new game selects, whose view holds their field.
+        let person_id = PersonId(2);
+        let selected = GlobalState::find_person(state, person_id).unwrap();
+        let modifiers = modifier_ids(&selected.read_untracked());
+
+        GlobalState::trash_person(state, person_id).unwrap();
+        assert_eq!(state.read_untracked().city_of(person_id), None);
+        assert!(!everyone(state).contains(&person_id));
+        assert!(selected.try_read_untracked().is_none());
+        assert_eq!(state.read_untracked().validate(), []);
+
+        GlobalState::restore_person(state, CityId(1), person_id).unwrap();
+        assert_eq!(state.read_untracked().city_of(person_id), Some(CityId(1)));
+        assert!(population(state, CityId(1))
+            .trash()
+            .read_untracked()
+            .is_empty());
+        // The held field finds the person again, under the same id.
+        assert_eq!(selected.id().get_untracked(), person_id);
+        assert_eq!(modifier_ids(&selected.read_untracked()), modifiers);
+        assert!(Population::person(population(state, CityId(1)), person_id).is_ok());
+        assert_eq!(state.read_untracked().validate(), []);
+    });
+}
+
+#[test]
+fn purged_people_cannot_be_restored() {
+    with_state(SimConfig::default(), |state| {
+        GlobalState::continue_in_sandbox(state);
+        let person_id = PersonId(2);
+        let selected = GlobalState::find_person(state, person_id).unwrap();
+        GlobalState::trash_person(state, person_id).unwrap();
+
+        let retention_weeks = SimConfig::default().trash_retention_weeks;
+        let mut purged = Vec::new();
+        for _ in 0..=retention_weeks {
+            reset_mutation_counts();
+            GlobalState::finish_week(state);
+            let outcome = state.last_week_outcome().get_untracked().unwrap();
+            purged.extend(outcome.purged_people);
+        }
+
+        assert_eq!(purged, [person_id]);
+        assert_eq!(
+            GlobalState::restore_person(state, CityId(1), person_id),
+            Err(SimError::NotInTrash {
+                person_id,
+                city_id: CityId(1),
+            })
+        );
+        assert!(selected.try_read_untracked().is_none());
+        assert!(GlobalState::find_person(state, person_id).is_none());
+        assert_eq!(state.read_untracked().validate(), []);
+    });
+}
+
+// Writes during a read are only refused in debug builds.
+#[cfg(debug_assertions)]
+#[test]
+fn effects_writing_while_they_read_are_refused() {
+    init_executor();
+    with_state(SimConfig::default(), |state| {
+        let person = first_person(state);
+        let person_id = person.id().get_untracked();
+        let results = Arc::new(Mutex::new(Vec::new()));
+        // Reads the notes like a derived signal would, and then wrongly edits them.
+        Effect::new({
+            let results = results.clone();
+            move || {
+                let _scope = ReadScope::enter();
+                let notes = person.notes().get();
+                GlobalState::set_person_notes(state, person_id, format!("{notes}!"));
+                results
+                    .lock()
+                    .unwrap()
+                    .push(GlobalState::trash_person(state, person_id));
+            }
+        });
+        run_effects();
+
+        assert_eq!(*results.lock().unwrap(), [Err(SimError::WriteDuringRead)]);
+        assert_eq!(person.notes().get_untracked(), "");
+        assert_eq!(state.read_untracked().city_of(person_id), Some(CityId(1)));
+    });
+}
+
+/// Finish `weeks` weeks of `state` in sandbox mode, returning the state after each week
+/// as JSON with everyone in id order, so that only the vec order is ignored.
+fn play(state: GlobalState, weeks: u64) -> Vec<String> {
+    let normalized_json = |mut state: GlobalState| {
+        for city in &mut state.cities {
+            city.population.people.sort_by_key(|person| person.id);
+            city.population.rebuild_index();
+        }
+        state.to_json()
+    };
+    let owner = Owner::new();
+    let result = owner.with(|| {
+        let state = Store::new(state);
+        GlobalState::continue_in_sandbox(state);
+        (0..weeks)
+            .map(|_| {
+                reset_mutation_counts();
+                GlobalState::finish_week(state);
+                normalized_json(state.get_untracked())
+            })
+            .collect()
+    });
+    owner.cleanup();
+    result
+}
+
+#[test]
+fn the_order_people_were_added_in_does_not_change_the_game() {
+    let config = SimConfig {
+        migration_happiness_gap: 0.0,
+        migration_probability: 0.1,
+        ..SimConfig::default()
+    };
+    let in_order = GlobalState::new_debug_instance(config.clone());
+    let mut reversed = in_order.clone();
+    for city in &mut reversed.cities {
+        city.population.people.reverse();
+        city.population.rebuild_index();
+    }
+    assert_eq!(reversed.validate(), []);
+
+    let in_order = play(in_order, 50);
+    assert_eq!(in_order.len(), 50);
+    assert_eq!(in_order, play(reversed, 50));
+}
+
+#[test]
+fn iter_people_yields_each_person_under_their_own_key() {
+    with_state(SimConfig::default(), |state| {
+        let population = population(state, CityId(1));
+        // Out of id order, and with a gap, so that keys and positions differ.
+        population.write().people.reverse();
+        population.write().rebuild_index();
+        population.people().update_keys();
+        GlobalState::trash_person(state, PersonId(3)).unwrap();
+
+        let mut yielded = Vec::new();
+        let mut ids = state.ids().get_untracked();
+        for (person_id, person) in Population::iter_people(population) {
+            assert_eq!(person.read_untracked().id, person_id);
+            yielded.push(person_id);
+            // Added people are not yielded by an iterator made before.
+            Population::add_person(population, Person::create(0, &[], &mut ids));
+        }
+
+        let expected: Vec<_> = [5, 4, 2, 1].map(PersonId).into();
+        assert_eq!(yielded, expected);
+        let all: Vec<_> = Population::iter_people(population)
+            .map(|(person_id, person)| {
+                assert_eq!(person.id().get_untracked(), person_id);
+                person_id
+            })
+            .collect();
+        assert_eq!(all.len(), expected.len() * 2);
+        assert_eq!(all[..4], expected);
+        state.ids().set(ids);
+        assert_eq!(state.read_untracked().validate(), []);
+    });
+}
+
+// The mutation rate is only limited in debug builds.
+#[cfg(debug_assertions)]
+#[test]
+fn effects_adding_modifiers_they_read_are_stopped_at_the_limit() {
+    init_executor();
+    with_state(SimConfig::default(), |state| {
+        reset_mutation_counts();
+        let person = first_person(state);
+        let person_id = person.id().get_untracked();
+        let modifiers_before = person
+            .happiness()
+            .happiness_modifiers()
+            .read_untracked()
+            .len();
+        let runs = Arc::new(AtomicUsize::new(0));
+        // Every added modifier changes what the effect read, so it runs again.
+        Effect::new({
+            let runs = runs.clone();
+            move || {
+                runs.fetch_add(1, Ordering::Relaxed);
+                person.happiness().happiness_modifiers().track();
+                GlobalState::add_pinned_modifier(state, person_id, ModifierSpecId::DEFAULT, None);
+            }
+        });
+        run_effects();
+
+        let limit = DEFAULT_MUTATION_RATE_LIMIT as usize;
+        let modifiers_after = person
+            .happiness()
+            .happiness_modifiers()
+            .read_untracked()
+            .len();
+        assert_eq!(modifiers_after - modifiers_before, limit);
+        // The refused mutation changes nothing, which ends the loop.
+        assert_eq!(runs.load(Ordering::Relaxed), limit + 1);
+        assert_eq!(state.read_untracked().validate(), []);
+    });
+}
+
+/// Run a fast-forward of `weeks` weeks step by step, as its timeouts would,
+/// calling `between` after every chunk. Returns the number of chunks.
+fn fast_forward(
+    state: Store<GlobalState>,
+    weeks: u64,
+    mut between: impl FnMut(Store<GlobalState>),
+) -> u64 {
+    state.fast_forward().set(Some(FastForward {
+        progress: Progress {
+            done: 0,
+            total: weeks,
+        },
+        cancel_requested: false,
+    }));
+    let mut chunks = 0;
+    while GlobalState::fast_forward_step(state) {
+        chunks += 1;
+        between(state);
+    }
+    assert!(state.fast_forward().read_untracked().is_none());
+    chunks
+}
+
+#[test]
+fn fast_forwards_end_where_finishing_weeks_one_by_one_does() {
+    let weeks = 95;
+    let one_by_one = with_state(SimConfig::default(), |state| {
+        GlobalState::continue_in_sandbox(state);
+        for _ in 0..weeks {
+            reset_mutation_counts();
+            GlobalState::finish_week(state);
+        }
+        state.read_untracked().to_json()
+    });
+    let fast_forwarded = with_state(SimConfig::default(), |state| {
+        GlobalState::continue_in_sandbox(state);
+        let chunks = fast_forward(state, weeks, |_| reset_mutation_counts());
+        assert_eq!(chunks, weeks.div_ceil(FAST_FORWARD_CHUNK_WEEKS));
+        assert_eq!(state.week().get_untracked(), weeks);
+        state.read_untracked().to_json()
+    });
+    assert_eq!(fast_forwarded, one_by_one);
+}
+
+#[test]
+fn cancelled_fast_forwards_stop_after_a_whole_chunk() {
+    with_state(SimConfig::default(), |state| {
+        GlobalState::continue_in_sandbox(state);
+        let mut chunks = 0;
+        fast_forward(state, 100, |state| {
+            chunks += 1;
+            if chunks == 2 {
+                GlobalState::cancel_fast_forward(state);
+            }
+        });
+        assert_eq!(state.week().get_untracked(), 2 * FAST_FORWARD_CHUNK_WEEKS);
+        assert_eq!(state.read_untracked().validate(), []);
+    });
+}
+
+#[test]
+fn announcements_of_quiet_weeks_only_name_the_week() {
+    assert_eq!(WeekOutcome::new(3).announcement(), "Week 3 finished.");
+}
+
+#[test]
+fn announcements_count_migrations_purges_and_the_average() {
+    let migration = |id| Migration {
+        person_id: PersonId(id),
+        from: CityId(1),
+        to: CityId(2),
+    };
+    let outcome = WeekOutcome {
+        migrations: vec![migration(1)],
+        average_happiness: Some(0.644),
+        ..WeekOutcome::new(12)
+    };
+    assert_eq!(
+        outcome.announcement(),
+        "Week 12 finished: 1 person moved, average happiness 64%."
+    );
+
+    let outcome = WeekOutcome {
+        migrations: vec![migration(1), migration(2)],
+        purged_people: vec![PersonId(3)],
+        ..WeekOutcome::new(13)
+    };
+    assert_eq!(
+        outcome.announcement(),
+        "Week 13 finished: 2 people moved, 1 purged from the trash."
+    );
+}
+
+#[test]
+fn announcements_end_with_finished_goals_and_watcher_alerts() {
+    let kind = GoalKind::KeepPopulation {
+        min: 5,
+        until_week: 52,
+    };
+    let outcome = WeekOutcome {
+        finished_goals: vec![
+            Goal {
+                kind,
+                status: GoalStatus::Failed,
+            },
+            Goal {
+                kind,
+                status: GoalStatus::Completed,
+            },
+        ],
+        watcher_alerts: vec![WatcherAlert {
+            watcher_id: WatcherId(1),
+            person_id: PersonId(4),
+            total: 0.25,
+        }],
+        ..WeekOutcome::new(52)
+    };
+    assert_eq!(
+        outcome.announcement(),
+        "Week 52 finished. \
+         Goal failed: Keep at least 5 people until week 52. \
+         Goal completed: Keep at least 5 people until week 52. \
+         Person 4 is at 25% happiness."
+    );
+}
+
+#[test]
+fn configs_survive_a_storage_round_trip() {
+    let config = SimConfig {
+        modifier_decay_rate: 0.25,
+        migration_probability: 0.5,
+        trash_retention_weeks: 9,
+        disabled_systems: ["trash"].into(),
+        initial_modifiers: vec![
+            InitialModifier {
+                spec: ModifierSpecId::DEFAULT,
+                magnitude: Some(0.125),
+                duration: None,
+            },
+            InitialModifier {
+                spec: ModifierSpecId::NOVELTY,
+                magnitude: None,
+                duration: Some(3),
+            },
+        ],
+        happy_threshold: 0.7,
+        ..SimConfig::default()
+    };
+    let text = config.to_storage_string();
+    let parsed = SimConfig::from_storage_string(&text);
+    assert_eq!(parsed.to_storage_string(), text);
+    assert_eq!(parsed.initial_modifiers, config.initial_modifiers);
+    assert_eq!(parsed.disabled_systems, config.disabled_systems);
+}
+
+#[test]
+fn configs_fall_back_to_the_defaults_for_missing_and_invalid_values() {
+    let defaults = SimConfig::default();
+    assert_eq!(
+        SimConfig::from_storage_string("").to_storage_string(),
+        defaults.to_storage_string()
+    );
+
+    let parsed = SimConfig::from_storage_string(
+        "migration_probability=2\n\
+         trash_retention_weeks=soon\n\
+         modifier_decay_rate=0.5\n\
+         disabled_systems=trash,nonexistent\n\
+         initial_modifiers=1:0.25,99,1:5",
+    );
+    assert_eq!(parsed.migration_probability, defaults.migration_probability);
+    assert_eq!(parsed.trash_retention_weeks, defaults.trash_retention_weeks);
+    assert_eq!(parsed.modifier_decay_rate, 0.5);
+    assert_eq!(parsed.disabled_systems, ["trash"].into());
+    // Only the invalid entries are left out.
+    assert_eq!(
+        parsed.initial_modifiers,
+        [InitialModifier {
+            spec: ModifierSpecId::DEFAULT,
+            magnitude: Some(0.25),
+            duration: None,
+        }]
+    );
+}
+
+#[test]
+fn initial_modifiers_survive_a_storage_round_trip() {
+    for initial in [
+        InitialModifier {
+            spec: ModifierSpecId::DEFAULT,
+            magnitude: None,
+            duration: None,
+        },
+        InitialModifier {
+            spec: ModifierSpecId::DEFAULT,
+            magnitude: Some(0.75),
+            duration: Some(2),
+        },
+        InitialModifier {
+            spec: ModifierSpecId::NOVELTY,
+            magnitude: None,
+            duration: Some(10),
+        },
+    ] {
+        let text = initial.to_storage_string();
+        assert_eq!(
+            InitialModifier::from_storage_string(&text),
+            Some(initial),
+            "{text}"
+        );
+    }
+    // The trailing parts may be left out.
+    assert_eq!(
+        InitialModifier::from_storage_string("1"),
+        InitialModifier::from_storage_string("1:_:_")
+    );
+}
+
+#[test]
+fn invalid_initial_modifiers_are_rejected() {
+    // Unknown spec, out of range, too many parts, not numbers.
+    for text in ["99", "1:1.5", "1:_:_:_", "one", "1:much", "1:_:-1", ""] {
+        assert_eq!(InitialModifier::from_storage_string(text), None, "{text:?}");
+    }
+}
+
+#[test]
+fn ids_survive_a_text_round_trip() {
+    let person_id = PersonId(42);
+    assert_eq!(person_id.to_string().parse(), Ok(person_id));
+    let modifier_id = HappinessModifierId(7);
+    assert_eq!(modifier_id.to_string().parse(), Ok(modifier_id));
+}
+
+#[test]
+fn invalid_ids_are_rejected() {
+    assert_eq!("0".parse::<PersonId>(), Err(ParseIdError::Zero));
+    assert_eq!("0".parse::<HappinessModifierId>(), Err(ParseIdError::Zero));
+    for text in ["", "-1", "1.5", "person"] {
+        assert_eq!(text.parse::<PersonId>(), Err(ParseIdError::NotANumber));
+        assert_eq!(
+            text.parse::<HappinessModifierId>(),
+            Err(ParseIdError::NotANumber)
+        );
+    }
+}
+
+fn watcher(direction: CrossingDirection, scope: WatcherScope) -> Watcher {
+    Watcher {
+        id: WatcherId(1),
+        threshold: 0.5,
+        direction,
+        scope,
+        hysteresis: 0.25,
+        fired_for: BTreeSet::new(),
+    }
+}
+
+#[test]
+fn watchers_below_fire_once_until_the_person_recovers_past_the_hysteresis() {
+    let mut watcher = watcher(CrossingDirection::Below, WatcherScope::All);
+    let person_id = PersonId(1);
+    // On the threshold is not below it.
+    assert!(!watcher.observe(person_id, 0.5));
+    assert!(watcher.observe(person_id, 0.49));
+    assert!(!watcher.observe(person_id, 0.1));
+    // Back above the threshold but within the hysteresis does not rearm it.
+    assert!(!watcher.observe(person_id, 0.74));
+    assert!(!watcher.observe(person_id, 0.4));
+    // Exactly at the threshold plus the hysteresis counts as recovered.
+    assert!(!watcher.observe(person_id, 0.75));
+    assert!(watcher.observe(person_id, 0.4));
+    // Every person is tracked on their own.
+    assert!(watcher.observe(PersonId(2), 0.4));
+}
+
+#[test]
+fn watchers_above_fire_once_until_the_person_recovers_past_the_hysteresis() {
+    let mut watcher = watcher(CrossingDirection::Above, WatcherScope::All);
+    let person_id = PersonId(1);
+    assert!(!watcher.observe(person_id, 0.5));
+    assert!(watcher.observe(person_id, 0.51));
+    assert!(!watcher.observe(person_id, 0.26));
+    assert!(!watcher.observe(person_id, 0.6));
+    assert!(!watcher.observe(person_id, 0.25));
+    assert!(watcher.observe(person_id, 0.6));
+}
+
+#[test]
+fn watchers_of_one_person_ignore_everyone_else() {
+    let mut watcher = watcher(CrossingDirection::Below, WatcherScope::Person(PersonId(1)));
+    assert!(!watcher.observe(PersonId(2), 0.0));
+    assert!(watcher.observe(PersonId(1), 0.0));
+}
+
+#[test]
+fn happiness_bands_start_at_their_thresholds() {
+    let thresholds = [0.2, 0.4, 0.6, 0.8];
+    let cases = [
+        (-1.0, HappinessBand::Miserable),
+        (0.19, HappinessBand::Miserable),
+        (0.2, HappinessBand::Unhappy),
+        (0.39, HappinessBand::Unhappy),
+        (0.4, HappinessBand::Content),
+        (0.6, HappinessBand::Happy),
+        (0.79, HappinessBand::Happy),
+        (0.8, HappinessBand::Thriving),
+        (2.0, HappinessBand::Thriving),
+    ];
+    for (happiness, band) in cases {
+        assert_eq!(
+            HappinessBand::of(happiness, thresholds),
+            band,
+            "{happiness}"
+        );
+    }
+    // A band whose threshold is not below the next one's is skipped.
+    assert_eq!(
+        HappinessBand::of(0.5, [0.2, 0.6, 0.4, 0.8]),
+        HappinessBand::Happy
+    );
+}
+
+#[test]
+fn new_games_start_everyone_with_the_configured_modifiers() {
+    let config = SimConfig {
+        initial_modifiers: vec![
+            InitialModifier {
+                spec: ModifierSpecId::DEFAULT,
+                magnitude: None,
+                duration: None,
+            },
+            InitialModifier {
+                spec: ModifierSpecId::NOVELTY,
+                magnitude: Some(0.125),
+                duration: Some(3),
+            },
+        ],
+        ..SimConfig::default()
+    };
+    let expected = config.initial_modifiers.clone();
+    with_state(config, |state| {
+        let state = state.read_untracked();
+        let people: Vec<_> = state
+            .cities
+            .iter()
+            .flat_map(|city| &city.population.people)
+            .collect();
+        assert!(!people.is_empty());
+        for person in &people {
+            let modifiers = &person.happiness.happiness_modifiers;
+            assert_eq!(modifiers.len(), expected.len(), "{:?}", person.id);
+            for (modifier, initial) in modifiers.iter().zip(&expected) {
+                assert_eq!(modifier.spec, initial.spec);
+                assert_eq!(modifier.magnitude, initial.magnitude);
+                assert_eq!(modifier.created_week, 0);
+                assert!(!modifier.pinned);
+            }
+            // Without an override, the duration of the spec is used.
+            assert_eq!(
+                modifiers[0].remaining_weeks,
+                ModifierSpecId::DEFAULT.spec().unwrap().default_duration
+            );
+            assert_eq!(modifiers[1].remaining_weeks, Some(3));
+        }
+        let ids: HashSet<_> = people.iter().copied().flat_map(modifier_ids).collect();
+        assert_eq!(ids.len(), people.len() * expected.len());
+    });
+}
+
+#[test]
+fn explanations_add_up_to_the_total() {
+    with_state(SimConfig::default(), |state| {
+        let person = first_person(state);
+        let added = {
+            let ids = &mut state.write().ids;
+            let mut novelty = HappinessModifier::create(ModifierSpecId::NOVELTY, 0, ids);
+            novelty.magnitude = Some(-0.125);
+            let mut unknown = HappinessModifier::create(ModifierSpecId(99), 0, ids);
+            unknown.magnitude = Some(0.25);
+            [
+                HappinessModifier::create(ModifierSpecId::NOVELTY, 0, ids),
+                novelty,
+                unknown,
+            ]
+        };
+        person
+            .happiness()
+            .happiness_modifiers()
+            .write()
+            .extend(added);
+
+        let happiness = person.happiness().into();
+        let explanation = Happiness::explain(happiness);
+        assert_eq!(explanation.total, Happiness::total(happiness));
+        let steps: f64 = explanation
+            .modifiers
+            .iter()
+            .map(|modifier| modifier.happiness)
+            .sum();
+        assert_eq!(steps, explanation.total);
+        let ids: Vec<_> = explanation
+            .modifiers
+            .iter()
+            .map(|modifier| modifier.id)
+            .collect();
+        assert_eq!(ids, modifier_ids(&person.read_untracked()));
+        assert_eq!(
+            explanation
+                .modifiers
+                .iter()
+                .map(|modifier| modifier.is_override)
+                .collect::<Vec<_>>(),
+            [false, false, true, true]
+        );
+        // Everything but the modifier of an unknown kind is in a category.
+        let categorized: f64 = explanation.by_category.values().sum();
+        assert_eq!(categorized, explanation.total - 0.25);
+    });
+}
+
+/// Turn on the mutation journal of this thread, dropping what it recorded so far.
+#[cfg(any(debug_assertions, feature = "metrics"))]
+fn record_mutations() {
+    MUTATION_JOURNAL.with(|journal| {
+        journal.enabled.set(true);
+        journal.records.write().clear();
+    });
+}
+
+#[cfg(any(debug_assertions, feature = "metrics"))]
+fn recorded_mutations() -> Vec<MutationRecord> {
+    MUTATION_JOURNAL.with(|journal| journal.records.get_untracked().into())
+}
+
+/// A debug instance a few weeks in, with a failed goal and someone in the trash.
+fn populated_state(state: Store<GlobalState>, trashed: PersonId) {
+    for _ in 0..3 {
+        reset_mutation_counts();
+        GlobalState::finish_week(state);
+    }
+    GlobalState::trash_person(state, trashed).unwrap();
+    for _ in 0..3 {
+        reset_mutation_counts();
+        GlobalState::finish_week(state);
+    }
+    state.goals().write()[1].status = GoalStatus::Failed;
+    reset_mutation_counts();
+}
+
+#[test]
+fn resetting_modifiers_clears_them_and_leaves_everything_else() {
+    with_state(SimConfig::default(), |state| {
+        let trashed = PersonId(2);
+        populated_state(state, trashed);
+        let people = everyone(state);
+        let trash_before = population(state, CityId(1)).trash().get_untracked();
+
+        GlobalState::reset_modifiers(state);
+
+        for city in &state.read_untracked().cities {
+            for person in &city.population.people {
+                assert!(person.happiness.happiness_modifiers.is_empty());
+            }
+        }
+        assert_eq!(GlobalState::overall_happiness(state).1, Some(0.0));
+        assert_eq!(everyone(state), people);
+        assert_eq!(state.week().get_untracked(), 6);
+        let trash = population(state, CityId(1)).trash().get_untracked();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].1, trash_before[0].1);
+        assert_eq!(modifier_ids(&trash[0].0), modifier_ids(&trash_before[0].0));
+        assert_eq!(state.read_untracked().validate(), []);
+        assert!(state.invariant_violations().read_untracked().is_empty());
+    });
+}
+
+#[test]
+fn resetting_the_week_restarts_the_goals_and_the_trash_retention() {
+    with_state(SimConfig::default(), |state| {
+        let trashed = PersonId(2);
+        populated_state(state, trashed);
+        assert!(state.read_untracked().is_game_over());
+        let people = everyone(state);
+        let modifiers = modifier_ids(&first_person(state).read_untracked());
+
+        GlobalState::reset_week(state);
+
+        assert_eq!(state.week().get_untracked(), 0);
+        assert!(state.last_week_outcome().read_untracked().is_none());
+        assert!(state.modifier_stats().read_untracked().is_empty());
+        assert!(state
+            .goals()
+            .read_untracked()
+            .iter()
+            .all(|goal| goal.status == GoalStatus::InProgress));
+        assert!(!state.read_untracked().is_game_over());
+        assert_eq!(everyone(state), people);
+        assert_eq!(
+            modifier_ids(&first_person(state).read_untracked()),
+            modifiers
+        );
+        assert_eq!(
+            population(state, CityId(1)).trash().read_untracked()[0].1,
+            0
+        );
+        assert_eq!(state.read_untracked().validate(), []);
+        assert!(state.invariant_violations().read_untracked().is_empty());
+
+        // The weeks advance again, and the trash is kept as long as after a removal in week 0.
+        let retention_weeks = SimConfig::default().trash_retention_weeks;
+        let mut purged_in = None;
+        for week in 1..=retention_weeks + 1 {
+            reset_mutation_counts();
+            GlobalState::finish_week(state);
+            assert_eq!(state.week().get_untracked(), week);
+            let outcome = state.last_week_outcome().get_untracked().unwrap();
+            if outcome.purged_people.contains(&trashed) {
+                purged_in = Some(week);
+            }
+        }
+        assert_eq!(purged_in, Some(retention_weeks + 1));
+    });
+}
+
+#[test]
+fn untouched_games_fail_the_default_happiness_goal() {
+    let mut simulation = Simulation::new(SimConfig::default(), 7);
+    let outcomes = simulation.run(52);
+    assert_eq!(outcomes.len(), 52);
+    let finished = &outcomes.last().unwrap().finished_goals;
+    assert!(matches!(
+        finished[..],
+        [
+            Goal {
+                kind: GoalKind::ReachAverageHappiness { .. },
+                status: GoalStatus::Failed,
+            },
+            Goal {
+                kind: GoalKind::KeepPopulation { .. },
+                status: GoalStatus::Completed,
+            },
+        ]
+    ));
+    assert!(simulation.is_game_over());
+}
+
+#[test]
+fn raising_the_happiness_of_everyone_reaches_the_default_happiness_goal() {
+    with_state(SimConfig::default(), |state| {
+        let template = ModifierTemplate {
+            spec: ModifierSpecId::DEFAULT,
+            magnitude: Some(0.3),
+            pinned: true,
+        };
+        for city_id in [CityId(1), CityId(2)] {
+            GlobalState::apply_modifier_to_matching(state, city_id, |_| true, template).unwrap();
+        }
+        reset_mutation_counts();
+        GlobalState::finish_week(state);
+
+        let goals = state.goals().get_untracked();
+        assert!(matches!(
+            goals[0].kind,
+            GoalKind::ReachAverageHappiness { .. }
+        ));
+        assert_eq!(goals[0].status, GoalStatus::Completed);
+        assert_eq!(goals[1].status, GoalStatus::InProgress);
+        assert!(!state.read_untracked().is_game_over());
+    });
+}
+
+#[test]
+fn the_population_of_a_city_renders_as_in_the_fixture() {
+    let config = SimConfig {
+        initial_modifiers: vec![
+            InitialModifier {
+                spec: ModifierSpecId::DEFAULT,
+                magnitude: None,
+                duration: None,
+            },
+            InitialModifier {
+                spec: ModifierSpecId::NOVELTY,
+                magnitude: None,
+                duration: None,
+            },
+        ],
+        ..SimConfig::default()
+    };
+    // The debug instance always seeds its RNG the same way.
+    let state = GlobalState::new_debug_instance(config);
+
+    let html = render_html(state, (CityId(1), None), || {
+        let state = use_global_state();
+        for _ in 0..3 {
+            reset_mutation_counts();
+            GlobalState::finish_week(state);
+        }
+        GlobalState::trash_person(state, PersonId(3)).unwrap();
+        let city_id = CityId(1);
+        let on_select = Callback::new(|_: PersonId| {});
+        view! {
+            <LastWeekSummary />
+            <PopulationSummary city_id />
+            <PopulationTable city_id selected=Signal::stored(None) on_select />
+            <TrashPanel city_id />
+        }
+    });
+    assert_matches_fixture("population_after_3_weeks.html", &normalize_html(&html, &[]));
+}
+
+#[test]
+fn a_missing_person_renders_the_error_fallback() {
+    let state = GlobalState::new_debug_instance(SimConfig::default());
+    let html = render_html(state, (CityId(1), None), || {
+        view! { <PersonView city_id=CityId(1) person_id=PersonId(999) /> }
+    });
+    let html = normalize_html(&html, &[]);
+    assert!(
+        html.contains("<p class=\"error\">\n  person 999 does not exist\n</p>"),
+        "{html}"
+    );
+    // The components of the person are replaced, not rendered next to the errors.
+    assert!(!html.contains("<nav>"), "{html}");
+}
+
+#[test]
+fn added_modifiers_show_their_icon_in_the_explanation_and_a_toast() {
+    let state = GlobalState::new_debug_instance(SimConfig::default());
+    let with_icon = Arc::new(AtomicUsize::new(0));
+    let icon = ModifierSpecId::NOVELTY.icon();
+    let html = render_html(state, (CityId(1), None), {
+        let with_icon = Arc::clone(&with_icon);
+        move || {
+            let state = use_global_state();
+            let person = first_person(state);
+            let person_id = person.id().get_untracked();
+            let spec_id = ModifierSpecId::NOVELTY;
+            let modifier_id =
+                GlobalState::add_pinned_modifier(state, person_id, spec_id, None).unwrap();
+            show_added_modifier_toast(state, person_id, spec_id, modifier_id);
+            let count = person
+                .read_untracked()
+                .happiness
+                .happiness_modifiers
+                .iter()
+                .filter(|modifier| modifier.spec.icon() == icon)
+                .count();
+            with_icon.store(count, Ordering::Relaxed);
+            view! {
+                <HappinessExplanation city_id=CityId(1) person_id />
+                <ToastHost />
+            }
+        }
+    });
+    let html = normalize_html(&html, &[]);
+    let path = format!("<path fill-rule=\"evenodd\" d=\"{}\">", icon.path());
+    // Once per modifier in the explanation, and once in the toast.
+    assert_eq!(
+        html.matches(&path).count(),
+        with_icon.load(Ordering::Relaxed) + 1,
+        "{html}"
+    );
+    assert!(html.contains("Novelty added to person 1."), "{html}");
+}
+
+#[test]
+fn removing_a_modifier_undoes_adding_it() {
+    with_state(SimConfig::default(), |state| {
+        let person = first_person(state);
+        let person_id = person.id().get_untracked();
+        let before = modifier_ids(&person.read_untracked());
+        let modifier_id =
+            GlobalState::add_pinned_modifier(state, person_id, ModifierSpecId::NOVELTY, None)
+                .unwrap();
+        assert_eq!(
+            GlobalState::remove_modifier(state, person_id, modifier_id),
+            Ok(())
+        );
+        assert_eq!(modifier_ids(&person.read_untracked()), before);
+        assert_eq!(
+            GlobalState::remove_modifier(state, person_id, modifier_id),
+            Err(SimError::ModifierNotFound(modifier_id))
+        );
+    });
+}
+
+#[test]
+fn opening_a_context_menu_closes_the_open_one() {
+    let state = GlobalState::new_debug_instance(SimConfig::default());
+    let html = render_html(state, (CityId(1), None), || {
+        open_context_menu((1, 2), vec![ContextMenuAction::new("First", || {})]);
+        open_context_menu(
+            (30, 40),
+            vec![
+                ContextMenuAction::new("View", || {}),
+                ContextMenuAction::new("Remove", || {}),
+            ],
+        );
+        view! { <ContextMenuHost /> }
+    });
+    let html = normalize_html(&html, &[]);
+    assert_eq!(html.matches("role=\"menu\"").count(), 1, "{html}");
+    assert!(html.contains("left: 30px; top: 40px"), "{html}");
+    assert_eq!(html.matches("role=\"menuitem\"").count(), 2, "{html}");
+    assert!(!html.contains("First"), "{html}");
+}
+
+#[test]
+fn the_add_modifier_dialog_offers_every_kind() {
+    let state = GlobalState::new_debug_instance(SimConfig::default());
+    let html = render_html(state, (CityId(1), None), || {
+        open_dialog(DialogRequest::AddModifier {
+            person_id: PersonId(1),
+        });
+        view! { <DialogHost /> }
+    });
+    let html = normalize_html(&html, &[]);
+    assert!(html.contains("Add a modifier to person 1"), "{html}");
+    assert_eq!(
+        html.matches("<option").count(),
+        MODIFIER_SPECS.len(),
+        "{html}"
+    );
+    assert!(html.contains("<input type=\"number\">"), "{html}");
+}
+
+/// The ids in the first column of the population table rows of `html`, in order.
+fn listed_people(html: &str) -> Vec<u64> {
+    let lines: Vec<_> = normalize_html(html, &[])
+        .lines()
+        .map(str::trim)
+        .map(str::to_string)
+        .collect();
+    lines
+        .windows(3)
+        .filter(|window| window[0].starts_with("<tr class=\"person-row") && window[1] == "<td>")
+        .map(|window| window[2].parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn the_modifier_filter_and_the_minimum_change_narrow_the_population_together() {
+    let listed = |specs: &[ModifierSpecId], min_change: Option<f64>| {
+        let specs: BTreeSet<_> = specs.iter().copied().collect();
+        let html = render_html(
+            GlobalState::new_debug_instance(SimConfig::default()),
+            (CityId(1), None),
+            move || {
+                let state = use_global_state();
+                for id in 1..=3 {
+                    GlobalState::add_pinned_modifier(
+                        state,
+                        PersonId(id),
+                        ModifierSpecId::NOVELTY,
+                        None,
+                    )
+                    .unwrap();
+                }
+                let deltas = [Some(0.2), Some(0.01), None, Some(-0.2), Some(0.0)];
+                for (id, delta) in (1..).zip(deltas) {
+                    let person = GlobalState::find_person(state, PersonId(id)).unwrap();
+                    person.happiness().last_week_delta().set(delta);
+                }
+                let preferences = expect_context::<Store<UiPreferences>>();
+                preferences.modifier_filter().write().specs = specs;
+                preferences.min_population_change().set(min_change);
+                let on_select = Callback::new(|_: PersonId| {});
+                view! { <PopulationTable city_id=CityId(1) selected=Signal::stored(None) on_select /> }
+            },
+        );
+        listed_people(&html)
+    };
+
+    assert_eq!(listed(&[], None), [1, 2, 3, 4, 5]);
+    assert_eq!(listed(&[ModifierSpecId::NOVELTY], None), [1, 2, 3]);
+    assert_eq!(listed(&[], Some(0.1)), [1, 4]);
+    assert_eq!(listed(&[ModifierSpecId::NOVELTY], Some(0.1)), [1]);
+}
+
+#[test]
+fn filtering_ten_thousand_people_by_modifier_takes_a_few_milliseconds() {
+    const PEOPLE: usize = 10_000;
+    let config = SimConfig {
+        initial_modifiers: vec![
+            InitialModifier {
+                spec: ModifierSpecId::DEFAULT,
+                magnitude: None,
+                duration: None,
+            },
+            InitialModifier {
+                spec: ModifierSpecId::NOVELTY,
+                magnitude: None,
+                duration: None,
+            },
+        ],
+        ..SimConfig::default()
+    };
+    let mut ids = IdAllocator::default();
+    let people: Vec<_> = (0..PEOPLE)
+        .map(|_| Person::create(0, &config.initial_modifiers, &mut ids))
+        .collect();
+    let filter = ModifierFilter {
+        specs: BTreeSet::from([ModifierSpecId::NOVELTY]),
+        categories: BTreeSet::new(),
+    };
+
+    // The fastest of a few runs, so that a busy machine does not fail the test.
+    let fastest = (0..5)
+        .map(|_| {
+            let start = std::time::Instant::now();
+            let matching = filter.matching_people(&people);
+            let elapsed = start.elapsed();
+            assert_eq!(matching.map(|matching| matching.len()), Some(PEOPLE));
+            elapsed
+        })
+        .min()
+        .unwrap();
+    // Unoptimized builds are about ten times slower.
+    let limit = if cfg!(debug_assertions) {
+        Duration::from_millis(50)
+    } else {
+        Duration::from_millis(5)
+    };
+    assert!(fastest < limit, "{fastest:?} for {PEOPLE} people");
+}
+
+#[test]
+fn normalized_html_has_a_line_per_node_without_markers_and_varying_attributes() {
+    let html = "<div data-hk=\"0-1\" class=\"a > b\"><!><input type=\"checkbox\" checked>\
+                <span id=\"x\">  Text </span><br/></div>after";
+    assert_eq!(
+        normalize_html(html, &["id"]),
+        "<div class=\"a > b\">\n  \
+           <input type=\"checkbox\" checked>\n  \
+           <span>\n    Text\n  </span>\n  \
+           <br>\n\
+         </div>\n\
+         after\n"
+    );
+}
+
+#[test]
+fn decayed_modifiers_are_not_overrides_and_overrides_decay_from_their_new_magnitude() {
+    with_state(SimConfig::default(), |state| {
+        let config = SimConfig::default();
+        let person = first_person(state);
+        let mut ids = state.ids().get_untracked();
+        let novelty = HappinessModifier::create(ModifierSpecId::NOVELTY, 0, &mut ids);
+        let novelty_id = novelty.id;
+        person
+            .happiness()
+            .happiness_modifiers()
+            .write()
+            .push(novelty);
+        let explained = || {
+            Happiness::explain(person.happiness().into())
+                .modifiers
+                .into_iter()
+                .find(|modifier| modifier.id == novelty_id)
+                .unwrap()
+        };
+
+        Happiness::finish_week(person, 1, &config, &mut ModifierStats::new(), &mut ids);
+        let decayed = explained();
+        assert!((decayed.happiness - 0.27).abs() < 1e-12);
+        assert!(!decayed.is_override);
+
+        Happiness::set_modifier_magnitude(person.happiness().into(), novelty_id, 0.5).unwrap();
+        let set = explained();
+        assert_eq!(set.happiness, 0.5);
+        assert!(set.is_override);
+        Happiness::finish_week(person, 2, &config, &mut ModifierStats::new(), &mut ids);
+        assert!((explained().happiness - 0.45).abs() < 1e-12);
+    });
+}
+
+#[test]
+fn pinned_modifiers_decay_but_do_not_expire() {
+    with_state(SimConfig::default(), |state| {
+        let config = SimConfig::default();
+        let person = first_person(state);
+        let person_id = person.id().get_untracked();
+        GlobalState::add_pinned_modifier(state, person_id, ModifierSpecId::NOVELTY, None);
+        GlobalState::add_pinned_modifier(state, person_id, ModifierSpecId::DEFAULT, Some(0.25));
+        let pinned = || -> Vec<_> {
+            person
+                .happiness()
+                .happiness_modifiers()
+                .read_untracked()
+                .iter()
+                .filter(|modifier| modifier.pinned)
+                .map(|modifier| (modifier.spec, modifier.current_magnitude()))
+                .collect()
+        };
+
+        let mut ids = state.ids().get_untracked();
+        for week in 1..=3 {
+            Happiness::finish_week(person, week, &config, &mut ModifierStats::new(), &mut ids);
+        }
+        // The default modifier would expire after a week, and does not decay.
+        let [(ModifierSpecId::NOVELTY, novelty), (ModifierSpecId::DEFAULT, default)] = pinned()[..]
+        else {
+            panic!("unexpected pinned modifiers {:?}", pinned());
+        };
+        assert!((novelty - 0.3 * 0.9f64.powi(3)).abs() < 1e-12);
+        assert_eq!(default, 0.25);
+    });
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "metrics"))]
+fn clearing_the_events_forgets_the_history_and_leaves_the_game() {
+    with_state(SimConfig::default(), |state| {
+        record_mutations();
+        GlobalState::set_rng_audit(state, true);
+        let trashed = PersonId(2);
+        populated_state(state, trashed);
+        assert!(state.last_week_outcome().read_untracked().is_some());
+        assert!(!state.rng_audit_log().read_untracked().is_empty());
+        assert!(!recorded_mutations().is_empty());
+        let before = state.get_untracked().to_json();
+
+        GlobalState::clear_events(state);
+
+        assert!(state.last_week_outcome().read_untracked().is_none());
+        assert!(state.rng_audit_log().read_untracked().is_empty());
+        let origins: Vec<_> = recorded_mutations()
+            .iter()
+            .map(|record| record.origin)
+            .collect();
+        assert_eq!(origins, ["GlobalState::clear_events"]);
+        // Nothing that is saved changed, the week and the failed goal included.
+        assert_eq!(state.get_untracked().to_json(), before);
+        assert!(state.read_untracked().is_game_over());
+        assert_eq!(state.read_untracked().validate(), []);
+        assert!(state.invariant_violations().read_untracked().is_empty());
+    });
+}
+
+#[test]
+fn loading_a_save_keeps_the_local_config_unless_asked_not_to() {
+    let saved_config = SimConfig {
+        migration_probability: 0.5,
+        ..SimConfig::default()
+    };
+    let local_config = SimConfig {
+        trash_retention_weeks: 9,
+        ..SimConfig::default()
+    };
+    let saved = GlobalState::new_debug_instance(saved_config.clone()).to_json();
+
+    let kept = GlobalState::from_save(&saved, local_config.clone(), false).unwrap();
+    assert_eq!(
+        kept.config.to_storage_string(),
+        local_config.to_storage_string()
+    );
+    let replaced = GlobalState::from_save(&saved, local_config, true).unwrap();
+    assert_eq!(
+        replaced.config.to_storage_string(),
+        saved_config.to_storage_string()
+    );
+
+    let mut simulation = Simulation::new(SimConfig::default(), 1);
+    simulation.load_json(&saved, false).unwrap();
+    assert!(simulation
+        .state_json()
+        .contains("migration_probability=0.05"));
+    simulation.load_json(&saved, true).unwrap();
+    assert!(simulation
+        .state_json()
+        .contains("migration_probability=0.5"));
+}
+
+#[test]
+fn loaded_games_continue_the_save_once() {
+    let owner = Owner::new();
+    owner.with(|| {
+        let mut saved = GlobalState::new_debug_instance(SimConfig::default());
+        saved.week = 12;
+        let json = saved.to_json();
+        let generation = GameGeneration::new();
+        generation.load_game(GlobalState::from_save(&json, SimConfig::default(), true).unwrap());
+
+        let (state, selection) = generation.next_game(ReadOnly(true));
+        assert_eq!(state.to_json(), json);
+        assert!(state.read_only);
+        assert_eq!(selection, (saved.cities[0].id, None));
+        // The next game after that is a new one again.
+        assert!(generation.loaded.with_value(Option::is_none));
+    });
+    owner.cleanup();
+}
+
+#[test]
+fn configs_are_copied_as_ron() {
+    let config = SimConfig {
+        trash_retention_weeks: 9,
+        disabled_systems: ["trash"].into(),
+        initial_modifiers: vec![
+            InitialModifier {
+                spec: ModifierSpecId::DEFAULT,
+                magnitude: None,
+                duration: None,
+            },
+            InitialModifier {
+                spec: ModifierSpecId::NOVELTY,
+                magnitude: Some(0.25),
+                duration: Some(3),
+            },
+        ],
+        happy_threshold: 1.0,
+        ..SimConfig::default()
+    };
+    assert_eq!(
+        config.to_ron(),
+        "SimConfig(
+    modifier_decay_rate: 0.1,
+    modifier_decay_threshold: 0.01,
+    migration_happiness_gap: 0.1,
+    migration_probability: 0.05,
+    trash_retention_weeks: 9,
+    disabled_systems: [\"trash\"],
+    initial_modifiers: [
+        (spec: 1, magnitude: None, duration: None),
+        (spec: 2, magnitude: Some(0.25), duration: Some(3)),
+    ],
+    unhappy_threshold: 0.2,
+    content_threshold: 0.4,
+    happy_threshold: 1.0,
+    thriving_threshold: 0.8,
+)
+"
+    );
+}
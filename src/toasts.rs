@@ -0,0 +1,182 @@
+//! Short-lived notices at the bottom of the page, rendered by one [`ToastHost`].
+//!
+//! Actions that can be reverted run right away and offer an undo in a toast,
+//! see [`show_undo_toast`], instead of asking for confirmation first.
+
+use std::{sync::Arc, time::Duration};
+
+use leptos::prelude::*;
+use reactive_stores::Store;
+
+use crate::{styles, Icon, IconId};
+
+/// How long a toast is shown, and so how long its undo is offered.
+pub const TOAST_DURATION: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ToastId(u64);
+
+/// Reverts an action, or returns why it could not.
+type Undo = Arc<dyn Fn() -> Result<(), String> + Send + Sync>;
+
+#[derive(Clone)]
+struct Toast {
+    message: String,
+    /// Shown before the message, e.g. the icon of the modifier the toast is about.
+    icon: Option<IconId>,
+    /// Taken when run, so each undo runs at most once.
+    /// Kept in an `Arc` rather than a `Callback`, so that it is dropped with the toast.
+    undo: Option<Undo>,
+}
+
+#[derive(Default, Clone, Store)]
+pub struct ToastManager {
+    /// The shown toasts, the oldest first.
+    toasts: Vec<(ToastId, Toast)>,
+    next_id: u64,
+}
+
+/// Provides the [`ToastManager`] store that [`show_undo_toast`] and [`ToastHost`] use.
+pub fn provide_toasts() {
+    provide_context(Store::new(ToastManager::default()));
+}
+
+fn use_toasts() -> Store<ToastManager> {
+    use_context().unwrap_or_else(|| {
+        panic!("No Store<ToastManager> in context, call provide_toasts() in an ancestor")
+    })
+}
+
+/// Show `message` with an "Undo" button for [`TOAST_DURATION`].
+///
+/// Every call gets its own toast and timer. If `undo` fails, its error replaces the message
+/// until the toast expires. Expired toasts are removed together with their `undo`.
+pub fn show_undo_toast(
+    message: String,
+    undo: impl Fn() -> Result<(), String> + Send + Sync + 'static,
+) {
+    push(message, None, Arc::new(undo));
+}
+
+/// Like [`show_undo_toast`], with `icon` before the message.
+pub fn show_icon_undo_toast(
+    icon: IconId,
+    message: String,
+    undo: impl Fn() -> Result<(), String> + Send + Sync + 'static,
+) {
+    push(message, Some(icon), Arc::new(undo));
+}
+
+fn push(message: String, icon: Option<IconId>, undo: Undo) {
+    let toasts = use_toasts();
+    let id = ToastId(toasts.next_id().get_untracked());
+    *toasts.next_id().write() += 1;
+    toasts.toasts().write().push((
+        id,
+        Toast {
+            message,
+            icon,
+            undo: Some(undo),
+        },
+    ));
+    schedule_removal(toasts, id, TOAST_DURATION);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn schedule_removal(toasts: Store<ToastManager>, id: ToastId, delay: Duration) {
+    set_timeout(move || remove(toasts, id), delay);
+}
+
+/// Without a browser there are no timers, so the toasts stay until the store is disposed.
+#[cfg(not(target_arch = "wasm32"))]
+fn schedule_removal(_toasts: Store<ToastManager>, _id: ToastId, _delay: Duration) {}
+
+fn remove(toasts: Store<ToastManager>, id: ToastId) {
+    // The store is gone if a new game started while the toast was shown.
+    toasts
+        .toasts()
+        .try_update(|toasts| toasts.retain(|(toast_id, _)| *toast_id != id));
+}
+
+fn undo(toasts: Store<ToastManager>, id: ToastId) {
+    let undo = toasts
+        .toasts()
+        .write()
+        .iter_mut()
+        .find(|(toast_id, _)| *toast_id == id)
+        .and_then(|(_, toast)| toast.undo.take());
+    let Some(undo) = undo else {
+        return;
+    };
+    match undo() {
+        Ok(()) => remove(toasts, id),
+        Err(error) => {
+            if let Some((_, toast)) = toasts
+                .toasts()
+                .write()
+                .iter_mut()
+                .find(|(toast_id, _)| *toast_id == id)
+            {
+                toast.message = error;
+            }
+        }
+    }
+}
+
+/// Renders the shown toasts, the newest at the bottom.
+#[component]
+pub fn ToastHost() -> impl IntoView {
+    let toasts = use_toasts();
+    let ids = move || {
+        toasts
+            .toasts()
+            .read()
+            .iter()
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>()
+    };
+
+    view! {
+        <div class=styles::TOASTS role="status" aria-live="polite">
+            <For
+                each=ids
+                key=|id| *id
+                children=move |id| {
+                    let message = move || {
+                        toasts
+                            .toasts()
+                            .read()
+                            .iter()
+                            .find(|(toast_id, _)| *toast_id == id)
+                            .map(|(_, toast)| toast.message.clone())
+                    };
+                    let icon = move || {
+                        toasts
+                            .toasts()
+                            .read()
+                            .iter()
+                            .find(|(toast_id, _)| *toast_id == id)
+                            .and_then(|(_, toast)| toast.icon)
+                            .map(|icon| view! { <Icon icon /> " " })
+                    };
+                    let has_undo = move || {
+                        toasts
+                            .toasts()
+                            .read()
+                            .iter()
+                            .any(|(toast_id, toast)| *toast_id == id && toast.undo.is_some())
+                    };
+                    view! {
+                        <div class=styles::TOAST>
+                            {icon}
+                            {message}
+                            <Show when=has_undo>
+                                " " <button on:click=move |_| undo(toasts, id)>Undo</button>
+                            </Show>
+                        </div>
+                    }
+                }
+            />
+        </div>
+    }
+}